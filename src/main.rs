@@ -1,19 +1,51 @@
 use anyhow::{Context, Result};
-use cargo_metadata::{MetadataCommand, Package};
-use clap::{Parser, ValueEnum};
+use cargo_metadata::{CargoOpt, Metadata, MetadataCommand, Package, PackageId};
+use clap::{Parser, Subcommand, ValueEnum};
 use futures::stream::{FuturesUnordered, StreamExt};
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
 use owo_colors::OwoColorize;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::path::PathBuf;
-use std::sync::Arc;
-use std::time::Duration;
-use tracing::{debug, warn};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::io::AsyncWriteExt;
+use tokio::time::Instant;
+use tracing::{Level, debug, warn};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::prelude::*;
 use url::Url;
 
-const GITHUB_GRAPHQL_URL: &str = "https://api.github.com/graphql";
-const USER_AGENT: &str = "cargo-sponsor";
+const GITHUB_API_URL: &str = "https://api.github.com";
+/// The REST API version requests pin with `X-GitHub-Api-Version`, unless `--github-api-version`
+/// asks for another.
+const GITHUB_API_VERSION: &str = "2022-11-28";
+/// `--github-host`, set once at startup. Repository URLs on this host are the ones looked up
+/// through the GitHub API; unset (as in tests), it is github.com.
+static GITHUB_HOST: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+/// Points every GitHub request at another base URL, e.g. a mock server in integration tests.
+const GITHUB_API_URL_ENV: &str = "CARGO_SPONSOR_GITHUB_API_URL";
+const GITLAB_API_URL: &str = "https://gitlab.com/api/v4";
+const CRATES_IO_API_URL: &str = "https://crates.io/api/v1";
+/// Overrides [`CRATES_IO_API_URL`], e.g. for a mock server.
+const CRATES_IO_API_URL_ENV: &str = "CARGO_SPONSOR_CRATES_IO_URL";
+/// crates.io asks automated clients for at most one request per second.
+const CRATES_IO_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+const DEPS_DEV_API_URL: &str = "https://api.deps.dev/v3";
+/// Overrides [`DEPS_DEV_API_URL`], e.g. for a mock server.
+const DEPS_DEV_API_URL_ENV: &str = "CARGO_SPONSOR_DEPS_DEV_URL";
+/// Where a GitLab project may keep its funding file, in the order we look.
+const GITLAB_FUNDING_FILES: &[&str] =
+    &[".gitlab/FUNDING.yml", ".github/FUNDING.yml", "FUNDING.yml"];
+const USER_AGENT: &str = concat!("cargo-sponsor/", env!("CARGO_PKG_VERSION"));
+const KEYRING_SERVICE: &str = "cargo-sponsor";
+/// Set to anything to never print first-run hints, like `--no-hints`.
+const NO_HINTS_ENV: &str = "CARGO_SPONSOR_NO_HINTS";
+/// Left in the state directory once the missing-token hint has been shown.
+const TOKEN_HINT_MARKER: &str = "token-hint-shown";
 
 #[derive(Parser)]
 #[command(name = "cargo")]
@@ -27,6 +59,89 @@ enum OutputFormat {
     #[default]
     Rich,
     Json,
+    Github,
+    Markdown,
+    Urls,
+    /// TOML comments to paste next to a cargo-deny config.
+    CargoDeny,
+    /// Comma-separated values with each package's license; every package with `--full`.
+    Csv,
+    /// An OPML outline of the projects' repositories, for feed readers.
+    Opml,
+    /// An RSS 2.0 feed with one item per project, linking to its funding page.
+    Rss,
+    /// A short Markdown comment for a bot to post on a pull request; pairs with `--since`.
+    PrComment,
+    /// A Mermaid flowchart from the workspace members to the projects they depend on.
+    Mermaid,
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Code hosts we know how to query for funding information.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Host {
+    Github,
+    /// gitlab.com, read through its REST API. Set `GITLAB_TOKEN` for private projects.
+    Gitlab,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum GroupBy {
+    Topic,
+    /// One entry per repository owner, with their crates listed beneath the shared link.
+    Owner,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Partition {
+    /// Projects payable through GitHub Sponsors first, then those needing another platform.
+    GithubSponsors,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SortKey {
+    Name,
+    Sponsors,
+    Dependents,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Store a GitHub token in the system keyring
+    Login,
+    /// Remove the GitHub token from the system keyring
+    Logout,
+    /// Scan once, then serve the results as JSON and an HTML page on localhost
+    Serve {
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
+    /// List the funding platforms links are recognized as, and the URLs that identify them
+    Platforms,
+    /// Combine the `--output json` of earlier scans into one report, without any network access
+    Merge {
+        /// JSON files written by `cargo sponsor --output json`
+        #[arg(required = true, value_name = "FILE")]
+        files: Vec<PathBuf>,
+    },
+    /// Scan, then record each project's funding links in a `funding.lock` to commit
+    Lock {
+        /// Where to write it [default: funding.lock in the workspace root]
+        #[arg(long, value_name = "PATH")]
+        lock_file: Option<PathBuf>,
+    },
+    /// Scan, then fail if the funding links differ from the `funding.lock`
+    Check {
+        /// The file `cargo sponsor lock` wrote [default: funding.lock in the workspace root]
+        #[arg(long, value_name = "PATH")]
+        lock_file: Option<PathBuf>,
+    },
 }
 
 #[derive(Parser)]
@@ -36,22 +151,445 @@ enum OutputFormat {
     about = "Find sponsorship links for your dependencies"
 )]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
     #[arg(long, default_value = ".")]
     manifest_path: PathBuf,
     #[arg(long, default_value = "rich")]
     output: OutputFormat,
     #[arg(long)]
     top_level_only: bool,
-    #[arg(long, default_value = "10")]
+    /// Also scan the workspace's own crates, not just their dependencies
+    #[arg(long)]
+    include_self: bool,
+    /// Lookups in flight at once, at least 1; values above 64 are lowered to 64
+    #[arg(long, default_value = "10", value_parser = parse_concurrency)]
     concurrency: usize,
+    /// Stop fetching after this long (e.g. "90s", "5m") and show partial results
+    #[arg(long, value_parser = humantime::parse_duration)]
+    max_duration: Option<Duration>,
+    /// Report every scanned package with its repository and funding status (JSON output only)
+    #[arg(long)]
+    full: bool,
+    /// Order results by package name, sponsor count, or number of dependents (highest first)
+    #[arg(long)]
+    sort: Option<SortKey>,
+    /// Flip the `--sort` order, e.g. least sponsored first
+    #[arg(long, requires = "sort")]
+    reverse: bool,
+    /// Drop projects whose repository hasn't been pushed to within this long (e.g. "180days")
+    #[arg(long, value_parser = humantime::parse_duration)]
+    active_within: Option<Duration>,
+    /// Read the GitHub token from this file instead of the environment
+    #[arg(long)]
+    token_file: Option<PathBuf>,
+    /// GitHub tokens to spread requests across, each with its own rate limit (comma-separated or
+    /// repeatable); replaces the usual token lookup
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "TOKEN",
+        conflicts_with = "token_file"
+    )]
+    token: Vec<String>,
+    /// How often to retry a rate-limited request before giving up; 0 fails on the first throttle
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = DEFAULT_MAX_RETRIES,
+        value_parser = clap::value_parser!(u32).range(0..=10)
+    )]
+    max_retries: u32,
+    /// Leave at least this many GitHub API requests unused, waiting for the reset if needed
+    #[arg(long)]
+    rate_limit_reserve: Option<u64>,
+    /// Give up on a lookup rather than wait longer than this for a rate limit (e.g. "5m")
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+    max_backoff: Option<Duration>,
+    /// Show at most this many results, after sorting
+    #[arg(long)]
+    limit: Option<usize>,
+    /// Scan a published crate (`name` or `name@version`) from crates.io instead of a local project
+    #[arg(
+        long = "crate",
+        value_name = "NAME[@VERSION]",
+        conflicts_with = "manifest_path"
+    )]
+    crate_spec: Option<String>,
+    /// Use a package's `homepage` when it has no `repository` and the homepage is a GitHub repo
+    #[arg(long)]
+    use_homepage_fallback: bool,
+    /// Format for diagnostic log lines on stderr
+    #[arg(long, default_value = "text")]
+    log_format: LogFormat,
+    /// Also write the JSON report to this file, whatever --output is
+    #[arg(long, value_name = "PATH")]
+    also_json: Option<PathBuf>,
+    /// Also write a Markdown table to this file, whatever --output is
+    #[arg(long, value_name = "PATH")]
+    also_markdown: Option<PathBuf>,
+    /// Write aggregate funding metrics without any crate or repository names to this file
+    #[arg(long, value_name = "PATH")]
+    export_anon: Option<PathBuf>,
+    /// For repos without declared funding, look for funding platform links in the README
+    #[arg(long)]
+    scan_readme: bool,
+    /// Fetch one repository at a time, in dependency order, for byte-identical output across runs
+    #[arg(long, hide = true)]
+    deterministic: bool,
+    /// List results in the order dependencies are declared in Cargo.toml, transitive ones after
+    #[arg(long, conflicts_with = "sort")]
+    preserve_order: bool,
+    /// Split the rich table into sections, e.g. by GitHub repository topic
+    #[arg(long)]
+    group_by: Option<GroupBy>,
+    /// Only query these code hosts (comma-separated); defaults to every supported host
+    #[arg(long, value_delimiter = ',', default_value = "github,gitlab")]
+    hosts: Vec<Host>,
+    /// Fetch and show the cheapest monthly GitHub Sponsors tier for each owner
+    #[arg(long)]
+    show_tiers: bool,
+    /// Program that receives each result as JSON on stdin and prints a JSON object of extra fields
+    #[arg(long, value_name = "PROGRAM")]
+    enrich_cmd: Option<PathBuf>,
+    /// Don't show progress bars
+    #[arg(long, short)]
+    quiet: bool,
+    /// Only analyze these workspace members and their dependencies (repeatable)
+    #[arg(long, short, value_name = "SPEC")]
+    package: Vec<String>,
+    /// Only analyze this dependency (`name` or `name@version`) and everything it pulls in
+    #[arg(long, value_name = "SPEC", conflicts_with = "top_level_only")]
+    subtree: Option<String>,
+    /// Analyze every workspace member, not just the default members
+    #[arg(long, conflicts_with = "package")]
+    workspace: bool,
+    /// Link the owner's GitHub Sponsors page when a repo declares no funding but its owner has a listing
+    #[arg(long)]
+    include_owner_sponsors: bool,
+    /// Abbreviate large sponsor counts in the rich table, e.g. 12.5k (JSON always has the raw number)
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, value_name = "BOOL")]
+    humanize: bool,
+    /// Skip packages whose repository URL matches this regex (repeatable)
+    #[arg(long, value_name = "REGEX", value_parser = Regex::new)]
+    exclude_repo_url: Vec<Regex>,
+    /// Print only the funding score (0-100) and exit
+    #[arg(long)]
+    score_only: bool,
+    /// Instead of sponsorable projects, list checked repositories without any funding option,
+    /// most depended-on first
+    #[arg(long)]
+    show_unfunded_popular: bool,
+    /// Instead of sponsorable projects, list funding links by how many of the projects they cover
+    #[arg(long, conflicts_with = "show_unfunded_popular")]
+    top_destinations: bool,
+    /// GitHub host to query and fetch `gh` credentials for, e.g. a GitHub Enterprise Server.
+    /// Only repositories on this host are looked up; github.com ones are then skipped
+    #[arg(long, value_name = "HOST", default_value = "github.com")]
+    github_host: String,
+    /// Only show repositories owned by a person (not an organization) with a GitHub Sponsors link
+    #[arg(long)]
+    prefer_personal: bool,
+    /// Print why this package was or wasn't included in the results
+    #[arg(long, value_name = "PACKAGE")]
+    explain: Option<String>,
+    /// Read `cargo metadata --format-version 1` output from stdin instead of running cargo
+    #[arg(long, conflicts_with_all = ["manifest_path", "crate_spec"])]
+    metadata_stdin: bool,
+    /// Scan the dependencies embedded in a binary built with `cargo auditable`
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["manifest_path", "crate_spec", "metadata_stdin", "features", "all_features", "no_default_features"]
+    )]
+    from_binary: Option<PathBuf>,
+    /// Print a copy-pasteable thank-you note for each owner instead of the report
+    #[arg(long)]
+    emit_thanks: bool,
+    /// Print what this Rhai script returns instead of the report; it gets the results as `results`
+    #[arg(long, value_name = "PATH", conflicts_with = "emit_thanks")]
+    format_script: Option<PathBuf>,
+    /// Print a Markdown funding badge for each sponsorable crate instead of the report
+    #[arg(long, conflicts_with_all = ["emit_thanks", "format_script"])]
+    emit_badges: bool,
+    /// After the report, open every project's primary funding link in the browser
+    #[arg(long)]
+    open_all: bool,
+    /// List every funding link in the rich table instead of the first plus a count
+    #[arg(long)]
+    show_all_links: bool,
+    /// Report the GitHub API budget left after the scan, and log why lookups failed
+    #[arg(long, short)]
+    verbose: bool,
+    /// Refuse to query more than this many repositories without confirmation
+    #[arg(long, value_name = "N", default_value_t = 500)]
+    max_packages: usize,
+    /// Check this crate even if `.cargo-sponsor-ignore` lists it or its repository (repeatable)
+    #[arg(long, value_name = "NAME")]
+    include: Vec<String>,
+    /// Only check build-time dependencies: build-dependencies, proc-macros, and what they pull in
+    #[arg(long)]
+    only_build_deps: bool,
+    /// Split the rich table into two sections by how the projects can be paid
+    #[arg(long, value_name = "KIND", conflicts_with = "group_by")]
+    partition: Option<Partition>,
+    /// Split the rich table into priority tiers by how many packages use each project
+    #[arg(long, conflicts_with_all = ["group_by", "partition"])]
+    tiers: bool,
+    /// Lower bounds of the `--tiers` tiers, highest first (e.g. 10,3: 10+, 3-9, below 3)
+    #[arg(
+        long,
+        value_name = "N,N,...",
+        value_delimiter = ',',
+        default_value = "10,3",
+        requires = "tiers"
+    )]
+    tier_thresholds: Vec<usize>,
+    /// Only check dependencies added or upgraded in Cargo.lock since this git ref
+    #[arg(long, value_name = "GIT_REF")]
+    since: Option<String>,
+    /// Only show projects with at least this many sponsors
+    #[arg(long, value_name = "N")]
+    min_sponsors: Option<u32>,
+    /// Count owners without a GitHub Sponsors listing as 0 sponsors when sorting and filtering
+    #[arg(long)]
+    treat_unknown_as_zero: bool,
+    /// Append finished lookups to this JSON-lines file and skip the ones already in it
+    #[arg(long, value_name = "PATH")]
+    checkpoint: Option<PathBuf>,
+    /// Keep the READMEs `--scan-readme` reads from GitHub with their ETags in this directory and
+    /// revalidate them on later runs; unchanged ones (HTTP 304) don't count against the rate limit
+    #[arg(long, value_name = "DIR", requires = "scan_readme")]
+    http_cache: Option<PathBuf>,
+    /// Resolve the dependency graph with these features enabled, as `cargo build --features`
+    #[arg(
+        long,
+        short = 'F',
+        value_name = "FEATURES",
+        value_delimiter = ',',
+        conflicts_with_all = ["metadata_stdin", "crate_spec"]
+    )]
+    features: Vec<String>,
+    /// Resolve the dependency graph with every feature enabled
+    #[arg(long, conflicts_with_all = ["features", "metadata_stdin", "crate_spec"])]
+    all_features: bool,
+    /// Resolve the dependency graph without the default features
+    #[arg(long, conflicts_with_all = ["metadata_stdin", "crate_spec"])]
+    no_default_features: bool,
+    /// Fail if any record can't be serialized to JSON, instead of leaving it out with a warning
+    #[arg(long)]
+    strict_json: bool,
+    /// Print how many repositories a scan would query and whether that fits the rate limit, then exit
+    #[arg(long)]
+    estimate: bool,
+    /// Add each package's documentation and homepage to the rich table and CSV
+    #[arg(long, conflicts_with = "columns")]
+    show_links: bool,
+    /// Show one row per package, even when several come from the same repository
+    #[arg(long)]
+    no_dedup: bool,
+    /// Report every version of a crate in the graph separately, instead of once per name
+    #[arg(long)]
+    per_version: bool,
+    /// Never print the hint about setting up a GitHub token (also `CARGO_SPONSOR_NO_HINTS`)
+    #[arg(long)]
+    no_hints: bool,
+    /// Where to look up funding. `deps-dev` needs no token; leave out `github` to skip GitHub
+    #[arg(
+        long,
+        value_name = "SOURCE,...",
+        value_delimiter = ',',
+        default_value = "github"
+    )]
+    source: Vec<DataSource>,
+    /// Which fields the rich table and CSV show, in order
+    #[arg(long, value_name = "COLUMN,...", value_delimiter = ',')]
+    columns: Option<Vec<Column>>,
+    /// Answer lookups from this JSON file mapping `owner/repo` to canned results, never the network
+    #[arg(long, value_name = "PATH")]
+    fixture: Option<PathBuf>,
+    /// User-Agent sent with every request [default: cargo-sponsor/<version>]
+    #[arg(long, value_name = "STRING")]
+    user_agent: Option<String>,
+    /// GitHub API version to request, sent as `X-GitHub-Api-Version`
+    #[arg(long, value_name = "VERSION", default_value = GITHUB_API_VERSION)]
+    github_api_version: String,
+    /// Check each crate's `repository` against crates.io and use crates.io's when they differ
+    #[arg(long)]
+    verify_repo: bool,
 }
 
-#[derive(Debug, Serialize)]
+/// How a package is used by the workspace. A package can be reached in several ways at once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum DepKind {
+    Normal,
+    /// Needed only to compile: build-dependencies, proc-macros, and their own dependencies.
+    Build,
+    Dev,
+}
+
+impl DepKind {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Build => "build",
+            Self::Dev => "dev",
+        }
+    }
+}
+
+const IGNORE_FILE: &str = ".cargo-sponsor-ignore";
+
+/// Exclusions shared through a `.cargo-sponsor-ignore` file at the workspace root. Each line is a
+/// crate name, or if it contains a `/`, a repository URL prefix such as `github.com/acme-internal/`.
+/// Blank lines and `#` comments are skipped.
+#[derive(Default)]
+struct IgnoreList {
+    names: HashSet<String>,
+    repo_prefixes: Vec<String>,
+    /// `--include`, which wins over any entry.
+    include: HashSet<String>,
+}
+
+impl IgnoreList {
+    fn load(workspace_root: &Path, include: &[String]) -> Result<Self> {
+        let path = workspace_root.join(IGNORE_FILE);
+        let mut list = Self {
+            include: include.iter().cloned().collect(),
+            ..Self::default()
+        };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(list),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        for line in text.lines() {
+            let entry = line.split('#').next().unwrap_or("").trim();
+            if entry.is_empty() {
+                continue;
+            }
+            if entry.contains('/') {
+                list.repo_prefixes.push(strip_scheme(entry).to_string());
+            } else {
+                list.names.insert(entry.to_string());
+            }
+        }
+        Ok(list)
+    }
+
+    /// The entry that excludes this package, unless `--include` names it.
+    fn matching_entry(&self, name: &str, repo_url: &str) -> Option<&str> {
+        if self.include.contains(name) {
+            return None;
+        }
+        if let Some(name) = self.names.get(name) {
+            return Some(name);
+        }
+        let repo_url = strip_scheme(repo_url);
+        self.repo_prefixes
+            .iter()
+            .find(|prefix| repo_url.starts_with(prefix.as_str()))
+            .map(String::as_str)
+    }
+}
+
+fn strip_scheme(url: &str) -> &str {
+    url.split_once("://").map_or(url, |(_, rest)| rest)
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 struct SponsorInfo {
     name: String,
+    /// The package's version, with `--per-version`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    /// The package's SPDX license expression, if it declares one.
+    license: Option<String>,
     repository: String,
     sponsor_links: Vec<String>,
+    /// How many sponsors the repository's owner account has. For an organization this covers
+    /// all of its projects, not just this one.
     sponsor_count: Option<u32>,
+    /// Packages elsewhere in the dependency graph that depend on a crate from this repository.
+    dependents: usize,
+    /// When the repository was last pushed to, as an RFC 3339 timestamp.
+    last_activity: Option<String>,
+    /// GitHub topics on the repository, e.g. `async` or `parser`.
+    topics: Vec<String>,
+    /// The owner's cheapest monthly GitHub Sponsors tier, with `--show-tiers`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_tier: Option<SponsorTier>,
+    /// Where `sponsor_links` came from.
+    source: FundingSource,
+    /// How the workspace uses the package (normal, build, dev). Empty without a resolved graph.
+    dep_kinds: Vec<DepKind>,
+    /// The package's `homepage`, if it declares one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    homepage: Option<String>,
+    /// The package's `documentation` URL, if it declares one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    documentation: Option<String>,
+    /// Whether the repository belongs to a user or an organization.
+    owner_type: OwnerType,
+    /// With `cargo sponsor merge`, every package the merged scans reported for the repository.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    packages: Vec<String>,
+    /// Fields added by `--enrich-cmd`.
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A service `--source` can look funding up from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum DataSource {
+    /// The repositories' FUNDING.yml and Sponsors listings, through the GitHub (and GitLab) API.
+    Github,
+    /// deps.dev's metadata on each package version: its repository and any funding links.
+    DepsDev,
+}
+
+impl SponsorInfo {
+    /// The package's name, as `name@version` with `--per-version`.
+    fn display_name(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{}@{version}", self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum FundingSource {
+    /// GitHub's `fundingLinks` and the repository's FUNDING.yml.
+    #[default]
+    Github,
+    /// Links to funding platforms found in the README by `--scan-readme`. Heuristic.
+    Readme,
+    /// The owner's GitHub Sponsors page, for a repo that declares no funding of its own
+    /// (`--include-owner-sponsors`).
+    OwnerListing,
+    /// A FUNDING.yml in a GitLab project, read through the GitLab API.
+    Gitlab,
+    /// `package.metadata.funding` in the crate's own Cargo.toml; needs no network.
+    Manifest,
+    /// Funding platform links deps.dev lists for the package version (`--source deps-dev`).
+    DepsDev,
+}
+
+/// The GraphQL `__typename` of a repository owner. Serialized in kebab-case like the other enums.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum OwnerType {
+    #[serde(alias = "User")]
+    User,
+    #[serde(alias = "Organization")]
+    Organization,
+    /// Bots, mannequins, and anything GitHub adds later.
+    #[default]
+    #[serde(other)]
+    Other,
 }
 
 #[derive(Debug, Deserialize)]
@@ -62,6 +600,21 @@ struct GitHubResponse {
 #[derive(Debug, Deserialize)]
 struct GitHubData {
     repository: Option<RepositoryData>,
+    /// Only selected for a URL naming just an account.
+    #[serde(default, rename = "repositoryOwner")]
+    repository_owner: Option<OwnerData>,
+    #[serde(rename = "rateLimit")]
+    rate_limit: Option<GraphqlRateLimit>,
+}
+
+/// The query's own `rateLimit` field, which reflects GraphQL point costs more accurately than
+/// the response headers.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphqlRateLimit {
+    cost: u64,
+    remaining: u64,
+    reset_at: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -69,6 +622,10 @@ struct GitHubData {
 struct RepositoryData {
     funding_links: Vec<FundingLink>,
     owner: OwnerData,
+    pushed_at: Option<String>,
+    funding_file: Option<Blob>,
+    root_funding_file: Option<Blob>,
+    repository_topics: Option<TopicConnection>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -76,11 +633,101 @@ struct FundingLink {
     url: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct TopicConnection {
+    nodes: Option<Vec<Option<TopicNode>>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TopicNode {
+    topic: Option<Topic>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Topic {
+    name: String,
+}
+
+/// A git object fetched by path. Only blobs carry `text`, and it's null for binary content.
+#[derive(Debug, Deserialize)]
+struct Blob {
+    #[serde(default)]
+    text: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct OwnerData {
+    #[serde(rename = "__typename")]
+    owner_type: OwnerType,
+    login: String,
+    /// Absent when the owner is neither a `User` nor an `Organization`, so neither fragment matched.
+    #[serde(default)]
     has_sponsors_listing: bool,
     sponsors: Option<SponsorConnection>,
+    sponsors_listing: Option<SponsorsListing>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SponsorsListing {
+    tiers: Option<TierConnection>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TierConnection {
+    nodes: Option<Vec<Option<TierNode>>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TierNode {
+    name: String,
+    monthly_price_in_dollars: u32,
+    is_one_time: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct SponsorTier {
+    name: String,
+    monthly_price_in_dollars: u32,
+}
+
+impl OwnerData {
+    /// `None` means the owner has no GitHub Sponsors listing (or the count is unknown);
+    /// `Some(0)` means they have a listing that nobody sponsors yet.
+    fn sponsor_count(&self) -> Option<u32> {
+        if !self.has_sponsors_listing {
+            return None;
+        }
+        self.sponsors.as_ref().map(|s| s.total_count)
+    }
+
+    /// The cheapest recurring tier, if `--show-tiers` selected the listing and it has any.
+    fn min_monthly_tier(&self) -> Option<SponsorTier> {
+        self.sponsors_listing
+            .as_ref()?
+            .tiers
+            .as_ref()?
+            .nodes
+            .iter()
+            .flatten()
+            .flatten()
+            .filter(|tier| !tier.is_one_time)
+            .min_by_key(|tier| tier.monthly_price_in_dollars)
+            .map(|tier| SponsorTier {
+                name: tier.name.clone(),
+                monthly_price_in_dollars: tier.monthly_price_in_dollars,
+            })
+    }
+}
+
+/// The owner-level part of a lookup, remembered per owner so repositories sharing one (all of
+/// `tokio-rs`, say) don't each pay for the owner's sponsor count and tiers again.
+#[derive(Clone)]
+struct OwnerSponsors {
+    has_sponsors_listing: bool,
+    sponsor_count: Option<u32>,
+    min_tier: Option<SponsorTier>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -89,318 +736,4748 @@ struct SponsorConnection {
     total_count: u32,
 }
 
+/// A unique GitHub repository to query, along with the first package that pointed at it.
+struct FetchTarget {
+    pkg_name: String,
+    /// Only with `--per-version`.
+    version: Option<String>,
+    license: Option<String>,
+    repo_url: String,
+    host: Host,
+    owner: String,
+    /// Empty when the URL names only a GitHub account; see [`extract_repo`].
+    repo: String,
+    dependents: usize,
+    /// `package.metadata.funding` of every package pointing at the repository.
+    manifest_links: Vec<String>,
+    /// With `--no-dedup`, the other packages from this repository, each reported as its own row.
+    shared_with: Vec<SharedPackage>,
+}
+
+/// Another package from a [`FetchTarget`]'s repository.
+struct SharedPackage {
+    name: String,
+    version: Option<String>,
+    license: Option<String>,
+}
+
+/// How many rows a repository that several packages come from turns into.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum RowsPer {
+    /// One, named after the first package (the default).
+    Repository,
+    /// One per package name (`--no-dedup`).
+    Package,
+    /// One per package name and version (`--per-version`).
+    Version,
+}
+
+/// Everything but `funding_links` may be left out of a `--fixture` file.
+#[derive(Clone, Deserialize, Serialize)]
 struct RepoInfo {
     funding_links: Vec<String>,
+    #[serde(default)]
     sponsor_count: Option<u32>,
+    #[serde(default)]
+    last_activity: Option<String>,
+    #[serde(default)]
+    topics: Vec<String>,
+    #[serde(default)]
+    min_tier: Option<SponsorTier>,
+    #[serde(default)]
+    source: FundingSource,
+    #[serde(default)]
+    owner_type: OwnerType,
 }
 
-const MAX_RETRIES: u32 = 3;
+/// What a single repository lookup turned up, keyed by `(owner, repo)` in [`FetchOutcome`].
+enum RepoStatus {
+    Funded(RepoInfo),
+    NoFunding,
+    /// The repository wasn't found, or there was no token to query it with.
+    Unavailable,
+    Failed(FailureKind, String),
+}
 
-async fn get_repo_sponsor_info(
-    client: &reqwest::Client,
-    owner: &str,
-    repo: &str,
-    token: Option<&Arc<str>>,
-) -> Result<Option<RepoInfo>> {
-    let Some(token) = token else {
-        return Ok(None);
-    };
+/// Broad categories of failed lookups, to tell systematic problems from transient ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum FailureKind {
+    /// Still rate limited after every retry.
+    RateLimited,
+    /// The token isn't authorized for the owning organization's SAML SSO.
+    SsoRequired,
+    /// The API answered with an unexpected HTTP status.
+    HttpStatus,
+    /// The response body wasn't what we expected.
+    Decode,
+    /// The request never got a response: DNS, TLS, timeouts, dropped connections.
+    Network,
+    Other,
+}
 
-    let query = r"
-        query($owner: String!, $repo: String!) {
-            repository(owner: $owner, name: $repo) {
-                fundingLinks { url }
-                owner {
-                    ... on User {
-                        hasSponsorsListing
-                        sponsors { totalCount }
-                    }
-                    ... on Organization {
-                        hasSponsorsListing
-                        sponsors { totalCount }
-                    }
-                }
-            }
+impl FailureKind {
+    fn label(self) -> &'static str {
+        match self {
+            Self::RateLimited => "rate limited",
+            Self::SsoRequired => "SSO authorization required",
+            Self::HttpStatus => "HTTP error",
+            Self::Decode => "unexpected response",
+            Self::Network => "network error",
+            Self::Other => "other",
         }
-    ";
-
-    let body = serde_json::json!({
-        "query": query,
-        "variables": { "owner": owner, "repo": repo }
-    });
+    }
 
-    let mut retries = 0;
-    loop {
-        let resp = client
-            .post(GITHUB_GRAPHQL_URL)
-            .header("Authorization", format!("Bearer {token}"))
-            .header("User-Agent", USER_AGENT)
-            .json(&body)
-            .send()
-            .await?;
+    /// Classifies any lookup error, including ones from hosts that don't use [`FetchError`].
+    fn of(error: &anyhow::Error) -> Self {
+        if let Some(e) = error.downcast_ref::<FetchError>() {
+            return e.kind();
+        }
+        match error.downcast_ref::<reqwest::Error>() {
+            Some(e) if e.is_decode() => Self::Decode,
+            Some(e) if e.is_status() => Self::HttpStatus,
+            Some(_) => Self::Network,
+            None => Self::Other,
+        }
+    }
+}
 
-        if resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
-            || resp.status() == reqwest::StatusCode::FORBIDDEN
-        {
-            if retries >= MAX_RETRIES {
-                anyhow::bail!("Rate limited after {MAX_RETRIES} retries for {owner}/{repo}");
-            }
+/// Why a GitHub GraphQL lookup failed.
+#[derive(Debug)]
+enum FetchError {
+    RateLimited {
+        retries: u32,
+    },
+    /// GitHub's abuse detection, separate from the hourly budget, kept throttling us.
+    SecondaryRateLimited {
+        retries: u32,
+    },
+    /// A rate limit asked for a longer wait than `--max-backoff` allows.
+    BackoffTooLong {
+        wait: Duration,
+    },
+    SsoRequired {
+        url: String,
+    },
+    HttpStatus(reqwest::StatusCode),
+    Decode(reqwest::Error),
+    Network(reqwest::Error),
+}
 
-            let retry_after = resp
-                .headers()
-                .get("retry-after")
-                .and_then(|v| v.to_str().ok())
-                .and_then(|v| v.parse::<u64>().ok())
-                .unwrap_or_else(|| 2u64.pow(retries));
+impl FetchError {
+    fn kind(&self) -> FailureKind {
+        match self {
+            Self::RateLimited { .. }
+            | Self::SecondaryRateLimited { .. }
+            | Self::BackoffTooLong { .. } => FailureKind::RateLimited,
+            Self::SsoRequired { .. } => FailureKind::SsoRequired,
+            Self::HttpStatus(_) => FailureKind::HttpStatus,
+            Self::Decode(_) => FailureKind::Decode,
+            Self::Network(_) => FailureKind::Network,
+        }
+    }
+}
 
-            debug!(
-                "Rate limited for {}/{}, waiting {}s (retry {}/{})",
-                owner,
-                repo,
-                retry_after,
-                retries + 1,
-                MAX_RETRIES
-            );
-            tokio::time::sleep(Duration::from_secs(retry_after)).await;
-            retries += 1;
-            continue;
+impl std::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RateLimited { retries } => write!(f, "rate limited after {retries} retries"),
+            Self::SecondaryRateLimited { retries } => write!(
+                f,
+                "hit GitHub's secondary rate limit after {retries} retries; try a lower --concurrency"
+            ),
+            Self::BackoffTooLong { wait } => write!(
+                f,
+                "rate limited for {}, longer than --max-backoff allows",
+                humantime::format_duration(*wait)
+            ),
+            Self::SsoRequired { url } => write!(
+                f,
+                "your GitHub token is not authorized for the SAML SSO organization that owns this repository; authorize it at {url}"
+            ),
+            Self::HttpStatus(status) => write!(f, "GitHub API error: {status}"),
+            Self::Decode(e) => write!(f, "unexpected response from GitHub: {e}"),
+            Self::Network(e) => write!(f, "request to GitHub failed: {e}"),
         }
+    }
+}
 
-        if !resp.status().is_success() {
-            anyhow::bail!("GitHub API error for {}/{}: {}", owner, repo, resp.status());
+impl std::error::Error for FetchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Decode(e) | Self::Network(e) => Some(e),
+            _ => None,
         }
+    }
+}
 
-        let data: GitHubResponse = resp.json().await?;
+struct FetchOutcome {
+    results: Vec<SponsorInfo>,
+    /// Every repository that was checked before the run ended.
+    statuses: HashMap<(String, String), RepoStatus>,
+    /// Whether the deadline cut the scan short.
+    truncated: bool,
+    /// Repositories that were checked and declare no funding, for `--show-unfunded-popular`.
+    unfunded: Vec<UnfundedRepo>,
+}
 
-        if let Some(data) = data.data
-            && let Some(repo_data) = data.repository
-        {
-            let links: Vec<String> = repo_data.funding_links.into_iter().map(|f| f.url).collect();
-            let sponsor_count = if repo_data.owner.has_sponsors_listing {
-                repo_data.owner.sponsors.map(|s| s.total_count)
-            } else {
-                None
-            };
-            return Ok(Some(RepoInfo {
-                funding_links: links,
-                sponsor_count,
-            }));
-        }
+#[derive(Serialize)]
+struct UnfundedRepo {
+    name: String,
+    repository: String,
+    dependents: usize,
+}
 
-        return Ok(None);
-    }
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum PackageStatus {
+    Sponsorable,
+    NoFunding,
+    Unavailable,
+    FetchFailed,
+    NotChecked,
+    NoRepository,
+    InvalidUrl,
+    NotGithub,
+    NotARepository,
 }
 
-fn extract_github_repo(repo_url: &str) -> Option<(String, String)> {
-    let url = Url::parse(repo_url).ok()?;
-    if url.host_str()? != "github.com" {
-        return None;
-    }
-    let segments: Vec<_> = url.path_segments()?.collect();
-    if segments.len() < 2 {
-        return None;
+/// One row of the `--full` report: a package whether or not it turned out to be sponsorable.
+#[derive(Debug, Serialize)]
+struct PackageReport {
+    name: String,
+    version: String,
+    license: Option<String>,
+    repository: Option<String>,
+    owner: Option<String>,
+    repo: Option<String>,
+    status: PackageStatus,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    sponsor_links: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sponsor_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_kind: Option<FailureKind>,
+}
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// More parallel lookups than this only trips GitHub's secondary rate limits faster.
+const MAX_CONCURRENCY: usize = 64;
+
+fn parse_concurrency(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.to_string()),
     }
-    let repo = segments[1].trim_end_matches(".git").to_string();
-    Some((segments[0].to_string(), repo))
 }
 
-fn get_github_token() -> Option<Arc<str>> {
-    std::env::var("GITHUB_TOKEN")
-        .ok()
-        .or_else(|| {
-            std::process::Command::new("gh")
-                .args(["auth", "token"])
-                .output()
-                .ok()
-                .filter(|output| output.status.success())
-                .and_then(|output| {
-                    String::from_utf8(output.stdout)
-                        .ok()
-                        .map(|s| s.trim().to_string())
-                })
-                .filter(|s| !s.is_empty())
-        })
-        .map(Arc::from)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Platform {
+    GithubSponsors,
+    OpenCollective,
+    Patreon,
+    KoFi,
+    BuyMeACoffee,
+    Liberapay,
+    Polar,
+    ThanksDev,
+    Tidelift,
+    IssueHunt,
+    LfxCrowdfunding,
+    CommunityBridge,
+    Custom,
 }
 
-fn collect_repos_to_fetch(
-    deps: &[&Package],
-) -> Vec<(String, String, String, String)> {
-    let mut seen_repos: HashSet<(String, String)> = HashSet::new();
-    let mut to_fetch = Vec::new();
+/// URL prefixes (host plus optional path, without `www.`) that identify each platform.
+const PLATFORM_PATTERNS: &[(Platform, &[&str])] = &[
+    (Platform::GithubSponsors, &["github.com/sponsors/"]),
+    (Platform::OpenCollective, &["opencollective.com/"]),
+    (Platform::Patreon, &["patreon.com/"]),
+    (Platform::KoFi, &["ko-fi.com/"]),
+    (Platform::BuyMeACoffee, &["buymeacoffee.com/"]),
+    (Platform::Liberapay, &["liberapay.com/"]),
+    (Platform::Polar, &["polar.sh/"]),
+    (Platform::ThanksDev, &["thanks.dev/"]),
+    (Platform::Tidelift, &["tidelift.com/"]),
+    (Platform::IssueHunt, &["issuehunt.io/"]),
+    (
+        Platform::LfxCrowdfunding,
+        &["crowdfunding.lfx.linuxfoundation.org/"],
+    ),
+    (Platform::CommunityBridge, &["funding.communitybridge.org/"]),
+];
 
-    for package in deps {
-        let Some(repo_url) = &package.repository else {
-            continue;
+impl Platform {
+    fn classify(link: &str) -> Self {
+        let Ok(url) = Url::parse(link) else {
+            return Self::Custom;
         };
-
-        let Some((repo_owner, repo_name)) = extract_github_repo(repo_url) else {
-            continue;
+        let Some(host) = url.host_str() else {
+            return Self::Custom;
         };
+        let location = format!("{}{}", host.trim_start_matches("www."), url.path());
+        PLATFORM_PATTERNS
+            .iter()
+            .find(|(_, patterns)| patterns.iter().any(|p| location.starts_with(p)))
+            .map_or(Self::Custom, |(platform, _)| *platform)
+    }
 
-        if seen_repos.contains(&(repo_owner.clone(), repo_name.clone())) {
-            continue;
+    fn label(self) -> &'static str {
+        match self {
+            Self::GithubSponsors => "GitHub Sponsors",
+            Self::OpenCollective => "Open Collective",
+            Self::Patreon => "Patreon",
+            Self::KoFi => "Ko-fi",
+            Self::BuyMeACoffee => "Buy Me a Coffee",
+            Self::Liberapay => "Liberapay",
+            Self::Polar => "Polar",
+            Self::ThanksDev => "thanks.dev",
+            Self::Tidelift => "Tidelift",
+            Self::IssueHunt => "IssueHunt",
+            Self::LfxCrowdfunding => "LFX Crowdfunding",
+            Self::CommunityBridge => "CommunityBridge",
+            Self::Custom => "Custom",
         }
-        seen_repos.insert((repo_owner.clone(), repo_name.clone()));
-        to_fetch.push((
-            package.name.to_string(),
-            repo_url.clone(),
-            repo_owner,
-            repo_name,
-        ));
     }
 
-    to_fetch
-}
-
-fn process_result(
-    results: &mut Vec<SponsorInfo>,
-    pkg_name: String,
-    repo_url: String,
-    owner: &str,
-    repo: &str,
-    result: Result<Option<RepoInfo>>,
-) {
-    match result {
-        Ok(Some(info)) if !info.funding_links.is_empty() => {
-            results.push(SponsorInfo {
-                name: pkg_name,
-                repository: repo_url,
-                sponsor_links: info.funding_links,
-                sponsor_count: info.sponsor_count,
-            });
-        }
-        Ok(_) => {}
-        Err(e) => {
-            warn!("Failed to fetch sponsor info for {owner}/{repo}: {e}");
-        }
+    /// Whether lookups report a sponsor count for this platform. Only GitHub Sponsors exposes one.
+    fn has_sponsor_counts(self) -> bool {
+        self == Self::GithubSponsors
     }
 }
 
-fn print_results(results: &[SponsorInfo]) {
-    if results.is_empty() {
-        println!("No sponsorable dependencies found.");
-        return;
+/// `cargo sponsor platforms`: the table [`Platform::classify`] works from.
+fn print_platforms(output: OutputFormat) -> Result<()> {
+    if matches!(output, OutputFormat::Json) {
+        let platforms: Vec<_> = PLATFORM_PATTERNS
+            .iter()
+            .map(|(platform, patterns)| {
+                serde_json::json!({
+                    "platform": platform.label(),
+                    "patterns": patterns,
+                    "sponsor_counts": platform.has_sponsor_counts(),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&platforms)?);
+        return Ok(());
     }
 
-    println!("\n  {}\n", "💝 Sponsorable Dependencies".cyan().bold());
-    println!(
-        "  Found {} projects you can support:\n",
-        results.len().bold()
-    );
-
-    let name_width = results
+    let name_width = PLATFORM_PATTERNS
         .iter()
-        .map(|r| r.name.len())
+        .map(|(platform, _)| platform.label().len())
         .max()
-        .unwrap_or(10)
-        .max(10);
-    let sponsors_width = 10;
-
+        .unwrap_or(8);
     println!(
-        "  {:<name_width$}  {:<sponsors_width$}  {}",
-        "Package".bold(),
-        "Sponsors".bold(),
-        "Link".bold(),
+        "  {}  {}  {}",
+        format!("{:<name_width$}", "Platform").bold(),
+        format!("{:<14}", "Sponsor counts").bold(),
+        "Recognized URLs".bold()
     );
+    for (platform, patterns) in PLATFORM_PATTERNS {
+        println!(
+            "  {:<name_width$}  {:<14}  {}",
+            platform.label(),
+            if platform.has_sponsor_counts() {
+                "yes"
+            } else {
+                "no"
+            },
+            patterns
+                .iter()
+                .map(|p| format!("{p}…"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    println!();
+    println!(
+        "  {}",
+        "Other links are shown as Custom, labelled with their host. A leading www. is ignored."
+            .dimmed()
+    );
+    Ok(())
+}
+
+/// Label for a result's first funding link, flagging links that were only found heuristically.
+fn primary_platform(info: &SponsorInfo) -> String {
+    let Some(link) = info.sponsor_links.first() else {
+        return "-".to_string();
+    };
+    match info.source {
+        FundingSource::Github
+        | FundingSource::OwnerListing
+        | FundingSource::Gitlab
+        | FundingSource::Manifest
+        | FundingSource::DepsDev => platform_label(link),
+        FundingSource::Readme => format!("{} (README)", platform_label(link)),
+    }
+}
+
+/// Short human-readable name for where a funding link points. Unknown hosts show the bare host.
+fn platform_label(link: &str) -> String {
+    match Platform::classify(link) {
+        Platform::Custom => Url::parse(link)
+            .ok()
+            .and_then(|url| {
+                url.host_str()
+                    .map(|h| h.trim_start_matches("www.").to_string())
+            })
+            .unwrap_or_else(|| link.to_string()),
+        platform => platform.label().to_string(),
+    }
+}
+
+/// Tracks GitHub's `X-RateLimit-*` headers across concurrent lookups so a run can leave part of
+/// the token's budget untouched for other tools.
+struct RateLimiter {
+    /// Stop issuing requests once only this many remain, until the window resets. Without
+    /// `--rate-limit-reserve` that is 0: a spent budget is waited out rather than run into.
+    reserve: Option<u64>,
+    /// `--max-backoff`: the longest this token will wait for a rate limit.
+    max_backoff: Option<Duration>,
+    state: Mutex<RateLimitState>,
+}
+
+#[derive(Default)]
+struct RateLimitState {
+    remaining: Option<u64>,
+    /// Unix time (seconds) at which the current window resets.
+    reset: Option<u64>,
+    /// The reset we last told the user we were waiting for, so concurrent lookups warn once.
+    announced_reset: Option<u64>,
+    /// GraphQL points spent by this run, as reported by `rateLimit.cost`.
+    cost: u64,
+    /// Set by a secondary rate limit: no request goes out on this token before then.
+    paused_until: Option<Instant>,
+    /// The pause we last reported progress on, so concurrent lookups report it once.
+    announced_pause: Option<Instant>,
+}
+
+impl RateLimiter {
+    fn new(reserve: Option<u64>, max_backoff: Option<Duration>) -> Self {
+        Self {
+            reserve,
+            max_backoff,
+            state: Mutex::new(RateLimitState::default()),
+        }
+    }
+
+    fn update(&self, headers: &reqwest::header::HeaderMap) {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok())
+        };
+        let mut state = self.state.lock().expect("rate limit state poisoned");
+        if let Some(remaining) = header("x-ratelimit-remaining") {
+            state.remaining = Some(remaining);
+        }
+        if let Some(reset) = header("x-ratelimit-reset") {
+            state.reset = Some(reset);
+        }
+    }
+
+    /// Records the budget a GraphQL response reported for itself. Takes precedence over the
+    /// headers of the same response, which arrive first.
+    fn record_graphql(&self, rate_limit: &GraphqlRateLimit) {
+        let mut state = self.state.lock().expect("rate limit state poisoned");
+        state.remaining = Some(rate_limit.remaining);
+        if let Ok(reset) = humantime::parse_rfc3339(&rate_limit.reset_at)
+            && let Ok(reset) = reset.duration_since(SystemTime::UNIX_EPOCH)
+        {
+            state.reset = Some(reset.as_secs());
+        }
+        state.cost += rate_limit.cost;
+    }
+
+    /// Whether the last response left no budget (or only the reserve) before a reset that is
+    /// still in the future.
+    fn is_exhausted(&self) -> bool {
+        let state = self.state.lock().expect("rate limit state poisoned");
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        matches!(
+            (state.remaining, state.reset),
+            (Some(remaining), Some(reset)) if self.at_reserve(remaining) && reset > now
+        )
+    }
+
+    /// Whether `remaining` leaves nothing to spend above the reserve.
+    fn at_reserve(&self, remaining: u64) -> bool {
+        remaining <= self.reserve.unwrap_or(0)
+    }
+
+    /// The last known remaining budget and reset time, plus the points this run has spent.
+    fn summary(&self) -> (Option<u64>, Option<u64>, u64) {
+        let state = self.state.lock().expect("rate limit state poisoned");
+        (state.remaining, state.reset, state.cost)
+    }
+
+    /// Holds every lookup on this token back for `wait`, so concurrent requests don't keep
+    /// tripping a secondary rate limit that one of them already hit.
+    fn pause(&self, wait: Duration) {
+        let mut state = self.state.lock().expect("rate limit state poisoned");
+        let until = Instant::now() + wait;
+        if state.paused_until.is_none_or(|current| current < until) {
+            state.paused_until = Some(until);
+        }
+    }
+
+    /// Fails with [`FetchError::BackoffTooLong`] if `--max-backoff` is shorter than `wait`.
+    fn check_backoff(&self, wait: Duration) -> Result<(), FetchError> {
+        match self.max_backoff {
+            Some(max) if wait > max => Err(FetchError::BackoffTooLong { wait }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Waits out a pause, then until the window resets if the remaining budget is down to the
+    /// reserve. Fails instead if `--max-backoff` is shorter than either wait.
+    async fn wait_for_budget(&self) -> Result<(), FetchError> {
+        let pause = {
+            let mut state = self.state.lock().expect("rate limit state poisoned");
+            state.paused_until.map(|until| {
+                let first = state.announced_pause != Some(until);
+                state.announced_pause = Some(until);
+                (until, first)
+            })
+        };
+        if let Some((until, first)) = pause {
+            let wait = until.saturating_duration_since(Instant::now());
+            self.check_backoff(wait)?;
+            sleep_with_notice(wait, "GitHub's secondary rate limit", first).await;
+        }
+        let wait = {
+            let mut state = self.state.lock().expect("rate limit state poisoned");
+            match (state.remaining, state.reset) {
+                (Some(remaining), Some(reset)) if self.at_reserve(remaining) => {
+                    let now = SystemTime::now()
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs();
+                    let wait = Duration::from_secs(reset.saturating_sub(now) + 1);
+                    self.check_backoff(wait)?;
+                    let first = state.announced_reset != Some(reset);
+                    if first {
+                        state.announced_reset = Some(reset);
+                        let reserving = self
+                            .reserve
+                            .map_or_else(String::new, |reserve| format!(" (reserving {reserve})"));
+                        warn!(
+                            "{remaining} GitHub API requests left{reserving}; waiting {} for the rate limit to reset",
+                            humantime::format_duration(wait)
+                        );
+                    }
+                    // Forget the stale budget; the first response after the reset refreshes it.
+                    state.remaining = None;
+                    Some((wait, first))
+                }
+                _ => None,
+            }
+        };
+        if let Some((wait, first)) = wait {
+            sleep_with_notice(wait, "the rate limit to reset", first).await;
+        }
+        Ok(())
+    }
+}
+
+/// How often a long rate-limit wait reports how much of it is left.
+const LONG_WAIT_NOTICE_EVERY: Duration = Duration::from_secs(30);
+
+/// Sleeps for `wait`. With `notify`, waits longer than [`LONG_WAIT_NOTICE_EVERY`] say how much
+/// is left every so often, so the run doesn't look frozen.
+async fn sleep_with_notice(wait: Duration, what: &str, notify: bool) {
+    let until = Instant::now() + wait;
+    if !notify || wait <= LONG_WAIT_NOTICE_EVERY {
+        tokio::time::sleep_until(until).await;
+        return;
+    }
+    loop {
+        let left = until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return;
+        }
+        tokio::time::sleep(left.min(LONG_WAIT_NOTICE_EVERY)).await;
+        let left = until.saturating_duration_since(Instant::now());
+        if !left.is_zero() {
+            // Whole seconds read better than humantime's nanosecond precision.
+            let left = Duration::from_secs(left.as_secs_f64().ceil() as u64);
+            warn!(
+                "Still waiting for {what}: {} left (cap it with --max-backoff)",
+                humantime::format_duration(left)
+            );
+        }
+    }
+}
+
+/// A GitHub token and the rate limit budget that belongs to it.
+struct GitHubToken {
+    token: Arc<str>,
+    limiter: RateLimiter,
+}
+
+/// Everything needed to talk to GitHub, shared by all concurrent lookups.
+struct GitHubClient {
+    http: reqwest::Client,
+    graphql_url: String,
+    /// Base URL for REST endpoints such as `/repos/{owner}/{repo}/readme`.
+    rest_url: String,
+    /// Sent as `PRIVATE-TOKEN` to the GitLab API, from `GITLAB_TOKEN`.
+    gitlab_token: Option<Arc<str>>,
+    /// Usually one; several with `--token`, taken in turn.
+    tokens: Vec<GitHubToken>,
+    next_token: AtomicUsize,
+    /// Also select the owner's sponsorship tiers (`--show-tiers`).
+    show_tiers: bool,
+    /// Fall back to the owner's GitHub Sponsors page (`--include-owner-sponsors`).
+    include_owner_sponsors: bool,
+    /// Retries per rate-limited request (`--max-retries`).
+    max_retries: u32,
+    /// Owner-level sponsor data already fetched this run, keyed by lowercased login.
+    owner_sponsors: Mutex<HashMap<String, OwnerSponsors>>,
+    /// `--http-cache`: where REST responses are kept for conditional requests.
+    http_cache: Option<PathBuf>,
+    /// `--fixture`: canned lookups by [`repo_key`], used instead of any API.
+    fixture: Option<HashMap<(String, String), RepoInfo>>,
+    /// `--github-api-version`.
+    api_version: String,
+}
+
+impl GitHubClient {
+    /// The next token in round-robin order, skipping ones whose budget is spent. When they all
+    /// are, the next one in turn is used and its limiter waits for the reset.
+    fn token(&self) -> Option<&GitHubToken> {
+        let count = self.tokens.len();
+        if count == 0 {
+            return None;
+        }
+        let start = self.next_token.fetch_add(1, Ordering::Relaxed);
+        (0..count)
+            .map(|offset| &self.tokens[(start + offset) % count])
+            .find(|t| !t.limiter.is_exhausted())
+            .or(self.tokens.get(start % count))
+    }
+
+    /// Adds the token and the pinned API version to a GitHub request.
+    fn authorize(&self, request: reqwest::RequestBuilder, token: &str) -> reqwest::RequestBuilder {
+        request
+            .header("Authorization", format!("Bearer {token}"))
+            .header("X-GitHub-Api-Version", &self.api_version)
+    }
+}
+
+async fn get_repo_sponsor_info(
+    github: &GitHubClient,
+    owner: &str,
+    repo: &str,
+) -> Result<Option<RepoInfo>> {
+    if github.tokens.is_empty() {
+        return Ok(None);
+    }
+    if repo.is_empty() {
+        return get_owner_sponsor_info(github, owner).await;
+    }
+
+    let query = r#"
+        query($owner: String!, $repo: String!, $withOwner: Boolean!, $withTiers: Boolean!) {
+            repository(owner: $owner, name: $repo) {
+                fundingLinks { url }
+                pushedAt
+                repositoryTopics(first: 10) { nodes { topic { name } } }
+                fundingFile: object(expression: "HEAD:.github/FUNDING.yml") { ... on Blob { text } }
+                rootFundingFile: object(expression: "HEAD:FUNDING.yml") { ... on Blob { text } }
+                owner {
+                    __typename
+                    login
+                    ... on User @include(if: $withOwner) {
+                        hasSponsorsListing
+                        sponsors { totalCount }
+                        sponsorsListing @include(if: $withTiers) { ...Tiers }
+                    }
+                    ... on Organization @include(if: $withOwner) {
+                        hasSponsorsListing
+                        sponsors { totalCount }
+                        sponsorsListing @include(if: $withTiers) { ...Tiers }
+                    }
+                }
+            }
+            rateLimit { cost remaining resetAt }
+        }
+
+        fragment Tiers on SponsorsListing {
+            tiers(first: 20) { nodes { name monthlyPriceInDollars isOneTime } }
+        }
+    "#;
+
+    let owner_key = owner.to_lowercase();
+    let cached_owner = github
+        .owner_sponsors
+        .lock()
+        .expect("owner cache poisoned")
+        .get(&owner_key)
+        .cloned();
+    let with_owner = cached_owner.is_none();
+    let body = serde_json::json!({
+        "query": query,
+        "variables": {
+            "owner": owner,
+            "repo": repo,
+            "withOwner": with_owner,
+            "withTiers": with_owner && github.show_tiers,
+        }
+    });
+
+    let Some(data) = post_graphql(github, &body, &format!("{owner}/{repo}")).await? else {
+        return Ok(None);
+    };
+
+    if let Some(repo_data) = data.repository {
+        let mut links: Vec<String> = repo_data.funding_links.into_iter().map(|f| f.url).collect();
+        // GitHub only reads `.github/FUNDING.yml` into `fundingLinks`, but plenty of repos keep
+        // the file at the root, so parse both and add anything GitHub didn't already report.
+        for text in [repo_data.funding_file, repo_data.root_funding_file]
+            .into_iter()
+            .flatten()
+            .filter_map(|blob| blob.text)
+        {
+            match parse_funding_yml(&text) {
+                Ok(file_links) => {
+                    for link in file_links {
+                        if !links.contains(&link) {
+                            links.push(link);
+                        }
+                    }
+                }
+                Err(e) => debug!("Ignoring unparseable FUNDING.yml in {owner}/{repo}: {e}"),
+            }
+        }
+        let owner_sponsors = match &cached_owner {
+            Some(cached) => cached.clone(),
+            None => {
+                let fetched = OwnerSponsors {
+                    has_sponsors_listing: repo_data.owner.has_sponsors_listing,
+                    sponsor_count: repo_data.owner.sponsor_count(),
+                    min_tier: repo_data.owner.min_monthly_tier(),
+                };
+                github
+                    .owner_sponsors
+                    .lock()
+                    .expect("owner cache poisoned")
+                    .insert(owner_key.clone(), fetched.clone());
+                fetched
+            }
+        };
+        let mut source = FundingSource::Github;
+        if links.is_empty() && github.include_owner_sponsors && owner_sponsors.has_sponsors_listing
+        {
+            links.push(format!(
+                "https://github.com/sponsors/{}",
+                repo_data.owner.login
+            ));
+            source = FundingSource::OwnerListing;
+        }
+        return Ok(Some(RepoInfo {
+            funding_links: links,
+            sponsor_count: owner_sponsors.sponsor_count,
+            last_activity: repo_data.pushed_at,
+            topics: repo_data
+                .repository_topics
+                .and_then(|t| t.nodes)
+                .unwrap_or_default()
+                .into_iter()
+                .flatten()
+                .filter_map(|node| node.topic.map(|t| t.name))
+                .collect(),
+            min_tier: owner_sponsors.min_tier,
+            source,
+            owner_type: repo_data.owner.owner_type,
+        }));
+    }
+
+    Ok(None)
+}
+
+/// Sends a GraphQL query, waiting out and retrying rate limits, and returns its `data`. `what`
+/// names the lookup in messages. `None` without a token.
+async fn post_graphql(
+    github: &GitHubClient,
+    body: &serde_json::Value,
+    what: &str,
+) -> Result<Option<GitHubData>> {
+    let mut retries = 0;
+    loop {
+        let Some(GitHubToken { token, limiter }) = github.token() else {
+            return Ok(None);
+        };
+        limiter.wait_for_budget().await?;
+        let resp = github
+            .authorize(github.http.post(&github.graphql_url), token)
+            .json(body)
+            .send()
+            .await
+            .map_err(FetchError::Network)?;
+        limiter.update(resp.headers());
+
+        if let Some(url) = sso_authorization_url(resp.headers()) {
+            return Err(FetchError::SsoRequired { url }.into());
+        }
+
+        if resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
+            || resp.status() == reqwest::StatusCode::FORBIDDEN
+        {
+            let retry_after = resp
+                .headers()
+                .get("retry-after")
+                .and_then(|v| v.to_str().ok())
+                .and_then(parse_retry_after);
+            // Secondary (abuse) limits are told apart from the hourly budget only by the message.
+            let secondary = resp
+                .text()
+                .await
+                .is_ok_and(|body| body.to_lowercase().contains("secondary rate limit"));
+
+            if retries >= github.max_retries {
+                return Err(if secondary {
+                    FetchError::SecondaryRateLimited { retries }
+                } else {
+                    FetchError::RateLimited { retries }
+                }
+                .into());
+            }
+
+            if secondary {
+                // GitHub asks for at least a minute between retries here, and repeated violations
+                // can get the token blocked, so back off far more than for the primary limit and
+                // pause every lookup sharing the token, not just this one.
+                let wait =
+                    retry_after.unwrap_or_else(|| Duration::from_secs(60 * 2u64.pow(retries)));
+                limiter.check_backoff(wait)?;
+                warn!(
+                    "Hit GitHub's secondary rate limit on {what}; pausing requests for {} (retry {}/{})",
+                    humantime::format_duration(wait),
+                    retries + 1,
+                    github.max_retries
+                );
+                limiter.pause(wait);
+            } else {
+                let wait = retry_after.unwrap_or_else(|| Duration::from_secs(2u64.pow(retries)));
+                limiter.check_backoff(wait)?;
+                debug!(
+                    "Rate limited for {what}, waiting {} (retry {}/{})",
+                    humantime::format_duration(wait),
+                    retries + 1,
+                    github.max_retries
+                );
+                sleep_with_notice(wait, &format!("{what}'s rate limit"), true).await;
+            }
+            retries += 1;
+            continue;
+        }
+
+        if !resp.status().is_success() {
+            return Err(FetchError::HttpStatus(resp.status()).into());
+        }
+
+        let data: GitHubResponse = resp.json().await.map_err(FetchError::Decode)?;
+        if let Some(rate_limit) = data.data.as_ref().and_then(|d| d.rate_limit.as_ref()) {
+            debug!(?rate_limit, "GraphQL rate limit for {what}");
+            limiter.record_graphql(rate_limit);
+        }
+        return Ok(data.data);
+    }
+}
+
+/// The lookup for a repository URL that names only a GitHub account: there are no funding links
+/// to read, so the account's own Sponsors listing is the funding source, if it has one.
+async fn get_owner_sponsor_info(github: &GitHubClient, owner: &str) -> Result<Option<RepoInfo>> {
+    let query = r#"
+        query($owner: String!, $withTiers: Boolean!) {
+            repositoryOwner(login: $owner) {
+                __typename
+                login
+                ... on User {
+                    hasSponsorsListing
+                    sponsors { totalCount }
+                    sponsorsListing @include(if: $withTiers) { ...Tiers }
+                }
+                ... on Organization {
+                    hasSponsorsListing
+                    sponsors { totalCount }
+                    sponsorsListing @include(if: $withTiers) { ...Tiers }
+                }
+            }
+            rateLimit { cost remaining resetAt }
+        }
+
+        fragment Tiers on SponsorsListing {
+            tiers(first: 20) { nodes { name monthlyPriceInDollars isOneTime } }
+        }
+    "#;
+    let body = serde_json::json!({
+        "query": query,
+        "variables": { "owner": owner, "withTiers": github.show_tiers },
+    });
+    let Some(owner_data) = post_graphql(github, &body, owner)
+        .await?
+        .and_then(|data| data.repository_owner)
+    else {
+        return Ok(None);
+    };
+    let funding_links = if owner_data.has_sponsors_listing {
+        vec![format!("https://github.com/sponsors/{}", owner_data.login)]
+    } else {
+        Vec::new()
+    };
+    Ok(Some(RepoInfo {
+        funding_links,
+        sponsor_count: owner_data.sponsor_count(),
+        last_activity: None,
+        topics: Vec::new(),
+        min_tier: owner_data.min_monthly_tier(),
+        source: FundingSource::OwnerListing,
+        owner_type: owner_data.owner_type,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+struct GitlabProject {
+    last_activity_at: Option<String>,
+    #[serde(default)]
+    topics: Vec<String>,
+    namespace: GitlabNamespace,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitlabNamespace {
+    /// `user` or `group`.
+    kind: String,
+}
+
+/// Looks up a gitlab.com project and reads the first funding file it has (see
+/// [`GITLAB_FUNDING_FILES`]). `owner` is the `gitlab.com/`-prefixed namespace from
+/// [`extract_repo`]. Returns `None` if the project doesn't exist or isn't visible to us.
+async fn get_gitlab_funding_info(
+    github: &GitHubClient,
+    owner: &str,
+    repo: &str,
+) -> Result<Option<RepoInfo>> {
+    let namespace = owner.strip_prefix("gitlab.com/").unwrap_or(owner);
+    let project_id = url::form_urlencoded::byte_serialize(format!("{namespace}/{repo}").as_bytes())
+        .collect::<String>();
+    let get = |path: String| {
+        let mut request = github
+            .http
+            .get(format!("{GITLAB_API_URL}/projects/{project_id}{path}"));
+        if let Some(token) = &github.gitlab_token {
+            request = request.header("PRIVATE-TOKEN", token.as_ref());
+        }
+        request.send()
+    };
+
+    let resp = get(String::new()).await?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !resp.status().is_success() {
+        anyhow::bail!("GitLab API error for {namespace}/{repo}: {}", resp.status());
+    }
+    let project: GitlabProject = resp.json().await?;
+
+    let mut links = Vec::new();
+    for file in GITLAB_FUNDING_FILES {
+        let file = url::form_urlencoded::byte_serialize(file.as_bytes()).collect::<String>();
+        let resp = get(format!("/repository/files/{file}/raw?ref=HEAD")).await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            continue;
+        }
+        if !resp.status().is_success() {
+            anyhow::bail!(
+                "GitLab API error for {namespace}/{repo} funding file: {}",
+                resp.status()
+            );
+        }
+        match parse_funding_yml(&resp.text().await?) {
+            Ok(found) => links = found,
+            Err(e) => warn!("Ignoring malformed funding file in {namespace}/{repo}: {e}"),
+        }
+        break;
+    }
+
+    Ok(Some(RepoInfo {
+        funding_links: links,
+        sponsor_count: None,
+        last_activity: project.last_activity_at,
+        topics: project.topics,
+        min_tier: None,
+        source: FundingSource::Gitlab,
+        owner_type: match project.namespace.kind.as_str() {
+            "user" => OwnerType::User,
+            "group" => OwnerType::Organization,
+            _ => OwnerType::Other,
+        },
+    }))
+}
+
+/// Fetches the repository README through the contents API and picks out links to known funding
+/// platforms. Used by `--scan-readme` for repos that don't declare funding any other way.
+async fn get_readme_funding_links(
+    github: &GitHubClient,
+    owner: &str,
+    repo: &str,
+) -> Result<Vec<String>> {
+    let Some(GitHubToken { token, limiter }) = github.token() else {
+        return Ok(Vec::new());
+    };
+
+    let url = format!("{}/repos/{owner}/{repo}/readme", github.rest_url);
+    let cache_path = github
+        .http_cache
+        .as_deref()
+        .map(|dir| http_cache_path(dir, &url));
+    let cached = cache_path.as_deref().and_then(read_http_cache);
+
+    limiter.wait_for_budget().await?;
+    let mut request = github
+        .authorize(github.http.get(&url), token)
+        .header("Accept", "application/vnd.github.raw+json");
+    if let Some(entry) = &cached {
+        request = request.header(reqwest::header::IF_NONE_MATCH, &entry.etag);
+    }
+    let resp = request.send().await?;
+    limiter.update(resp.headers());
+
+    if resp.status() == reqwest::StatusCode::NOT_MODIFIED
+        && let Some(entry) = cached
+    {
+        debug!("README of {owner}/{repo} unchanged, using the cached copy");
+        return Ok(extract_funding_urls(&entry.body));
+    }
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(Vec::new());
+    }
+    if !resp.status().is_success() {
+        anyhow::bail!(
+            "GitHub API error for {owner}/{repo} README: {}",
+            resp.status()
+        );
+    }
+
+    let etag = resp
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let body = resp.text().await?;
+    if let (Some(path), Some(etag)) = (&cache_path, etag) {
+        let entry = HttpCacheEntry { etag, body };
+        write_http_cache(path, &entry);
+        return Ok(extract_funding_urls(&entry.body));
+    }
+    Ok(extract_funding_urls(&body))
+}
+
+/// A REST response kept by `--http-cache`, revalidated with `If-None-Match` on the next run.
+/// GraphQL lookups are POSTs that GitHub doesn't answer with ETags, so only REST is cached.
+#[derive(Deserialize, Serialize)]
+struct HttpCacheEntry {
+    etag: String,
+    body: String,
+}
+
+/// One file per URL, named after it so the cache directory stays inspectable.
+fn http_cache_path(dir: &Path, url: &str) -> PathBuf {
+    let name: String = url
+        .trim_start_matches("https://")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    dir.join(format!("{name}.json"))
+}
+
+fn read_http_cache(path: &Path) -> Option<HttpCacheEntry> {
+    let text = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&text)
+        .inspect_err(|e| debug!("Ignoring unreadable cache entry {}: {e}", path.display()))
+        .ok()
+}
+
+/// Best effort: a cache that can't be written only costs the next run some quota.
+fn write_http_cache(path: &Path, entry: &HttpCacheEntry) {
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::write(path, serde_json::to_string(entry).unwrap_or_default()));
+    if let Err(e) = result {
+        warn!("Failed to write {}: {e}", path.display());
+    }
+}
+
+/// Finds URLs in free text (Markdown, HTML, or plain) that point at a recognized funding platform.
+fn extract_funding_urls(text: &str) -> Vec<String> {
+    let mut links: Vec<String> = Vec::new();
+    for (start, _) in text.match_indices("http") {
+        let rest = &text[start..];
+        if !rest.starts_with("https://") && !rest.starts_with("http://") {
+            continue;
+        }
+        let end = rest
+            .find(|c: char| c.is_whitespace() || "()[]<>\"'`".contains(c))
+            .unwrap_or(rest.len());
+        let candidate = rest[..end].trim_end_matches(['.', ',', ';', ':', '!']);
+        if Platform::classify(candidate) != Platform::Custom
+            && !links.iter().any(|l| l == candidate)
+        {
+            links.push(candidate.to_string());
+        }
+    }
+    links
+}
+
+/// Turns the entries of a FUNDING.yml file into funding URLs, in file order.
+///
+/// Files are hand-edited, so a leading byte-order mark and CRLF line endings are tolerated, and
+/// empty placeholder keys from GitHub's template are skipped.
+fn parse_funding_yml(text: &str) -> Result<Vec<String>, serde_yaml::Error> {
+    let text = text
+        .strip_prefix('\u{feff}')
+        .unwrap_or(text)
+        .replace("\r\n", "\n");
+    let Some(entries) = serde_yaml::from_str::<Option<serde_yaml::Mapping>>(&text)? else {
+        return Ok(Vec::new());
+    };
+
+    let mut links = Vec::new();
+    for (key, value) in entries {
+        let Some(key) = key.as_str() else {
+            continue;
+        };
+        let values = match value {
+            serde_yaml::Value::Sequence(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            let value = match value {
+                serde_yaml::Value::String(s) => s,
+                serde_yaml::Value::Number(n) => n.to_string(),
+                _ => continue,
+            };
+            let value = value.trim();
+            if value.is_empty() {
+                continue;
+            }
+            if let Some(link) = funding_yml_url(key, value) {
+                links.push(link);
+            }
+        }
+    }
+    Ok(links)
+}
+
+/// Maps a FUNDING.yml key and value to the URL GitHub would link to.
+fn funding_yml_url(key: &str, value: &str) -> Option<String> {
+    let url = match key {
+        "github" => format!("https://github.com/sponsors/{value}"),
+        "patreon" => format!("https://www.patreon.com/{value}"),
+        "open_collective" => format!("https://opencollective.com/{value}"),
+        "ko_fi" => format!("https://ko-fi.com/{value}"),
+        "tidelift" => format!("https://tidelift.com/funding/github/{value}"),
+        "community_bridge" => format!("https://funding.communitybridge.org/projects/{value}"),
+        "liberapay" => format!("https://liberapay.com/{value}"),
+        "issuehunt" => format!("https://issuehunt.io/r/{value}"),
+        "lfx_crowdfunding" => {
+            format!("https://crowdfunding.lfx.linuxfoundation.org/projects/{value}")
+        }
+        "polar" => format!("https://polar.sh/{value}"),
+        "buy_me_a_coffee" => format!("https://buymeacoffee.com/{value}"),
+        "thanks_dev" => format!("https://thanks.dev/{value}"),
+        "custom" if value.starts_with("http://") || value.starts_with("https://") => {
+            value.to_string()
+        }
+        "custom" => format!("https://{value}"),
+        _ => return None,
+    };
+    Some(url)
+}
+
+/// Parses a `Retry-After` value, which RFC 7231 allows to be either a number of seconds or an
+/// HTTP-date. A date in the past means "retry now".
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = httpdate::parse_http_date(value).ok()?;
+    Some(at.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+/// Returns the authorization URL when GitHub rejected the token because it isn't SSO-authorized.
+///
+/// The header looks like `X-GitHub-SSO: required; url=https://github.com/orgs/acme/sso?...`.
+/// A `partial-results` value only means some data was hidden, so it isn't treated as fatal.
+fn sso_authorization_url(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    let value = headers.get("x-github-sso")?.to_str().ok()?;
+    let mut parts = value.split(';').map(str::trim);
+    if parts.next()? != "required" {
+        return None;
+    }
+    let url = parts
+        .find_map(|part| part.strip_prefix("url="))
+        .unwrap_or("https://github.com/settings/tokens");
+    Some(url.to_string())
+}
+
+/// The `(owner, repo)` key for a repository on any host we query. GitHub owners are bare logins;
+/// GitLab owners are the full namespace prefixed with `gitlab.com/` (e.g. `gitlab.com/group/sub`)
+/// so they can't collide with a GitHub login. A URL naming only a GitHub account (some crates
+/// point `repository` at their author) has an empty `repo`, and is looked up by the account's
+/// Sponsors listing alone.
+fn extract_repo(repo_url: &str) -> Option<(Host, String, String)> {
+    if let Some((owner, repo)) = extract_github_repo(repo_url) {
+        return Some((Host::Github, owner, repo));
+    }
+    if let Some(owner) = extract_github_owner(repo_url) {
+        return Some((Host::Github, owner, String::new()));
+    }
+    let (namespace, project) = extract_gitlab_repo(repo_url)?;
+    Some((Host::Gitlab, format!("gitlab.com/{namespace}"), project))
+}
+
+/// The key repositories are deduplicated and looked up by. Owner and repository names are
+/// case-insensitive on both hosts, so `Owner/Repo` and `owner/repo` are the same project; the
+/// original casing is kept everywhere else for display.
+fn repo_key(owner: &str, repo: &str) -> (String, String) {
+    (owner.to_lowercase(), repo.to_lowercase())
+}
+
+/// Splits a gitlab.com project URL into its namespace (which may contain subgroups) and project
+/// name, ignoring anything after GitLab's `/-/` separator, as in `/-/tree/main`.
+fn extract_gitlab_repo(repo_url: &str) -> Option<(String, String)> {
+    let url = Url::parse(repo_url.trim()).ok()?;
+    if url.host_str()? != "gitlab.com" {
+        return None;
+    }
+    let segments: Vec<_> = url
+        .path_segments()?
+        .take_while(|s| *s != "-")
+        .filter(|s| !s.is_empty())
+        .collect();
+    let (project, namespace) = segments.split_last()?;
+    if namespace.is_empty() {
+        return None;
+    }
+    Some((
+        namespace.join("/"),
+        project.trim_end_matches(".git").to_string(),
+    ))
+}
+
+/// Whether `url` is on the GitHub host being queried ([`GITHUB_HOST`]), with or without `www.`.
+fn is_github_host(url: &Url) -> bool {
+    let host = GITHUB_HOST.get().map_or("github.com", String::as_str);
+    url.host_str()
+        .is_some_and(|h| h.strip_prefix("www.").unwrap_or(h) == host)
+}
+
+/// Splits a GitHub repository URL (github.com, or the `--github-host`) into `(owner, repo)`. Crates declare these in many shapes,
+/// so surrounding whitespace, `www.`, a query or fragment (`?tab=readme`, `#readme`), empty
+/// segments from trailing or doubled slashes, a `.git` suffix (`repo.git/`), and deeper paths
+/// like `/tree/main/crates/foo` are all tolerated.
+fn extract_github_repo(repo_url: &str) -> Option<(String, String)> {
+    let url = Url::parse(repo_url.trim()).ok()?;
+    if !is_github_host(&url) {
+        return None;
+    }
+    let mut segments = url.path_segments()?.filter(|s| !s.is_empty());
+    let owner = segments.next()?;
+    // `github.com/sponsors/<login>` and `github.com/orgs/<login>` are account pages.
+    if matches!(owner, "sponsors" | "orgs") {
+        return None;
+    }
+    let repo = segments.next()?;
+    let repo = repo.strip_suffix(".git").unwrap_or(repo);
+    if repo.is_empty() {
+        return None;
+    }
+    Some((owner.to_string(), repo.to_string()))
+}
+
+/// The account a `github.com/<login>` URL (or its `sponsors/` or `orgs/` page) names, for
+/// repository URLs that stop short of a repository.
+fn extract_github_owner(repo_url: &str) -> Option<String> {
+    let url = Url::parse(repo_url.trim()).ok()?;
+    if !is_github_host(&url) {
+        return None;
+    }
+    let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+    let login = match segments.as_slice() {
+        ["sponsors" | "orgs", login, ..] => login,
+        [login] if !matches!(*login, "sponsors" | "orgs") => login,
+        _ => return None,
+    };
+    Some(login.to_string())
+}
+
+/// Turns `--manifest-path` into the path of an existing `Cargo.toml`, explaining what was tried if not.
+fn resolve_manifest_path(path: &Path) -> Result<PathBuf> {
+    if path.is_dir() {
+        let manifest = path.join("Cargo.toml");
+        if !manifest.is_file() {
+            anyhow::bail!(
+                "No Cargo.toml found in {} (looked for {})",
+                path.display(),
+                manifest.display()
+            );
+        }
+        return Ok(manifest);
+    }
+    if !path.exists() {
+        anyhow::bail!("Manifest path {} does not exist", path.display());
+    }
+    if path.file_name() != Some(OsStr::new("Cargo.toml")) {
+        anyhow::bail!(
+            "Manifest path {} is not a Cargo.toml; pass the manifest or the directory containing it",
+            path.display()
+        );
+    }
+    Ok(path.to_path_buf())
+}
+
+/// The keyring entry for `host`'s token, so a GitHub Enterprise token never stands in for the
+/// github.com one or the other way around.
+fn keyring_entry(host: &str) -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, &host.to_lowercase())
+}
+
+fn get_keyring_token(host: &str) -> Option<String> {
+    match keyring_entry(host).and_then(|entry| entry.get_password()) {
+        Ok(token) => Some(token),
+        Err(keyring::Error::NoEntry) => None,
+        Err(e) => {
+            debug!("Could not read GitHub token from the system keyring: {e}");
+            None
+        }
+    }
+}
+
+fn login(host: &str) -> Result<()> {
+    let token = rpassword::prompt_password("GitHub token: ").context("Failed to read token")?;
+    let token = token.trim();
+    if token.is_empty() {
+        anyhow::bail!("No token entered");
+    }
+    keyring_entry(host)
+        .and_then(|entry| entry.set_password(token))
+        .context("Failed to store token in the system keyring")?;
+    eprintln!("Saved GitHub token for {host} to the system keyring.");
+    Ok(())
+}
+
+fn logout(host: &str) -> Result<()> {
+    match keyring_entry(host).and_then(|entry| entry.delete_credential()) {
+        Ok(()) => eprintln!("Removed GitHub token for {host} from the system keyring."),
+        Err(keyring::Error::NoEntry) => {
+            eprintln!("No GitHub token for {host} was stored in the system keyring.")
+        }
+        Err(e) => return Err(e).context("Failed to remove token from the system keyring"),
+    }
+    Ok(())
+}
+
+fn read_token_file(path: &Path) -> Result<String> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read token file {}", path.display()))?;
+    let token = contents.trim();
+    if token.is_empty() {
+        anyhow::bail!("Token file {} is empty", path.display());
+    }
+    Ok(token.to_string())
+}
+
+/// Resolves a published crate's dependency tree without a local project, by running
+/// `cargo metadata` on a throwaway package that depends on `name[@version]`.
+fn published_crate_metadata(spec: &str) -> Result<Metadata> {
+    let (name, version) = spec.split_once('@').unwrap_or((spec, "*"));
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        anyhow::bail!("Invalid crate name in --crate {spec}");
+    }
+    // Anything but an exact version (`1.*`, `^1`) would make a meaningless `=` pin.
+    if version != "*" && cargo_metadata::semver::Version::parse(version).is_err() {
+        anyhow::bail!("Invalid version in --crate {spec}: expected an exact version like 1.35.1");
+    }
+    let requirement = if version == "*" {
+        version.to_string()
+    } else {
+        format!("={version}")
+    };
+
+    let dir = tempfile::tempdir().context("Failed to create a scratch project")?;
+    let manifest_path = dir.path().join("Cargo.toml");
+    std::fs::write(
+        &manifest_path,
+        format!(
+            "[package]\nname = \"cargo-sponsor-crate-scan\"\nversion = \"0.0.0\"\nedition = \"2021\"\npublish = false\n\n[dependencies]\n{name} = \"{requirement}\"\n"
+        ),
+    )?;
+    std::fs::create_dir(dir.path().join("src"))?;
+    std::fs::write(dir.path().join("src/lib.rs"), "")?;
+
+    MetadataCommand::new()
+        .manifest_path(&manifest_path)
+        .exec()
+        .with_context(|| format!("Failed to resolve {spec} from crates.io"))
+}
+
+/// The dependency list `cargo auditable` embeds in a binary; only the fields we need.
+#[derive(Deserialize)]
+struct AuditableInfo {
+    packages: Vec<AuditablePackage>,
+}
+
+#[derive(Deserialize)]
+struct AuditablePackage {
+    name: String,
+    version: String,
+    source: String,
+    #[serde(default)]
+    root: bool,
+}
+
+/// Resolves the dependencies recorded in a `cargo auditable` binary, by running `cargo metadata`
+/// on a throwaway package that pins each crates.io dependency to the version that was built in.
+/// Also returns those `(name, version)` pairs, since the pins can pull in platform-specific
+/// crates that never made it into the binary.
+fn binary_metadata(path: &Path) -> Result<(Metadata, HashSet<LockedPackage>)> {
+    let json = auditable_info::json_from_file(path, auditable_info::Limits::default())
+        .with_context(|| format!("Failed to read dependency data from {}", path.display()))?;
+    let info: AuditableInfo = serde_json::from_str(&json)
+        .with_context(|| format!("Failed to parse dependency data from {}", path.display()))?;
+
+    let mut pins = Vec::new();
+    let mut skipped = 0;
+    for package in info.packages.iter().filter(|p| !p.root) {
+        let valid = !package.name.is_empty()
+            && package
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            && cargo_metadata::semver::Version::parse(&package.version).is_ok();
+        if package.source != "crates.io" || !valid {
+            skipped += 1;
+            continue;
+        }
+        let pin = LockedPackage {
+            name: package.name.clone(),
+            version: package.version.clone(),
+        };
+        if !pins.contains(&pin) {
+            pins.push(pin);
+        }
+    }
+    if skipped > 0 {
+        warn!(
+            "Skipping {skipped} {} in {} that {} not from crates.io",
+            if skipped == 1 {
+                "dependency"
+            } else {
+                "dependencies"
+            },
+            path.display(),
+            if skipped == 1 { "is" } else { "are" }
+        );
+    }
+
+    let metadata = match resolve_pins(&pins) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            // Usually one version that has since been yanked; find it by resolving each pin
+            // alone, so the rest of the binary can still be scanned.
+            debug!(
+                "Failed to resolve every dependency of {}: {e:#}",
+                path.display()
+            );
+            let (resolvable, unresolvable): (Vec<_>, Vec<_>) = pins
+                .into_iter()
+                .partition(|pin| resolve_pins(std::slice::from_ref(pin)).is_ok());
+            if resolvable.is_empty() {
+                return Err(e).with_context(|| {
+                    format!("Failed to resolve the dependencies of {}", path.display())
+                });
+            }
+            let names: Vec<String> = unresolvable
+                .iter()
+                .map(|pin| format!("{}@{}", pin.name, pin.version))
+                .collect();
+            warn!(
+                "Skipping {} {} of {} that crates.io can't resolve (yanked?): {}",
+                names.len(),
+                if names.len() == 1 {
+                    "dependency"
+                } else {
+                    "dependencies"
+                },
+                path.display(),
+                names.join(", ")
+            );
+            pins = resolvable;
+            resolve_pins(&pins).with_context(|| {
+                format!("Failed to resolve the dependencies of {}", path.display())
+            })?
+        }
+    };
+    Ok((metadata, pins.into_iter().collect()))
+}
+
+/// Runs `cargo metadata` on a throwaway package depending on exactly these crate versions.
+fn resolve_pins(pins: &[LockedPackage]) -> Result<Metadata> {
+    // Each pin gets its own key, so two versions of one crate can sit side by side.
+    let dependencies: String = pins
+        .iter()
+        .enumerate()
+        .map(|(i, pin)| {
+            format!(
+                "dep{i} = {{ package = \"{}\", version = \"={}\", default-features = false }}\n",
+                pin.name, pin.version
+            )
+        })
+        .collect();
+    let dir = tempfile::tempdir().context("Failed to create a scratch project")?;
+    let manifest_path = dir.path().join("Cargo.toml");
+    std::fs::write(
+        &manifest_path,
+        format!(
+            "[package]\nname = \"cargo-sponsor-binary-scan\"\nversion = \"0.0.0\"\nedition = \"2021\"\npublish = false\n\n[dependencies]\n{dependencies}"
+        ),
+    )?;
+    std::fs::create_dir(dir.path().join("src"))?;
+    std::fs::write(dir.path().join("src/lib.rs"), "")?;
+
+    Ok(MetadataCommand::new()
+        .manifest_path(&manifest_path)
+        .exec()?)
+}
+
+/// Where cargo-sponsor remembers things between runs: `$XDG_STATE_HOME/cargo-sponsor`, falling
+/// back to `~/.local/state/cargo-sponsor` (`%LOCALAPPDATA%\cargo-sponsor` on Windows).
+fn state_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/state")))
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))?;
+    Some(base.join("cargo-sponsor"))
+}
+
+/// Whether to show the missing-token hint: only on the first tokenless run, and never with
+/// `--no-hints`. Leaves the marker behind so later runs stay quiet.
+fn should_show_token_hint(no_hints: bool) -> bool {
+    if no_hints || std::env::var_os(NO_HINTS_ENV).is_some() {
+        return false;
+    }
+    // Without anywhere to remember it, keep showing the hint rather than never showing it.
+    let Some(dir) = state_dir() else {
+        return true;
+    };
+    let marker = dir.join(TOKEN_HINT_MARKER);
+    if marker.exists() {
+        return false;
+    }
+    if let Err(e) = std::fs::create_dir_all(&dir).and_then(|()| std::fs::write(&marker, "")) {
+        debug!(path = %marker.display(), "Failed to record that the token hint was shown: {e}");
+    }
+    true
+}
+
+fn get_github_token(token_file: Option<&Path>, host: &str) -> Result<Option<Arc<str>>> {
+    if let Some(path) = token_file {
+        return Ok(Some(Arc::from(read_token_file(path)?)));
+    }
+    Ok(std::env::var("GITHUB_TOKEN")
+        .ok()
+        .or_else(|| get_keyring_token(host))
+        .or_else(|| {
+            std::process::Command::new("gh")
+                .args(["auth", "token", "--hostname", host])
+                .output()
+                .ok()
+                .filter(|output| {
+                    if !output.status.success() {
+                        debug!(host, "gh has no token for this host");
+                    }
+                    output.status.success()
+                })
+                .and_then(|output| {
+                    String::from_utf8(output.stdout)
+                        .ok()
+                        .map(|s| s.trim().to_string())
+                })
+                .filter(|s| !s.is_empty())
+        })
+        .map(Arc::from))
+}
+
+/// Asks before querying more repositories than `--max-packages` allows. Without a terminal to ask
+/// on, the scan is refused.
+fn confirm_large_scan(total: usize, max: usize) -> Result<()> {
+    use std::io::{BufRead, IsTerminal, Write};
+
+    let message =
+        format!("This scan would query {total} repositories, more than --max-packages {max}");
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        anyhow::bail!("{message}; raise --max-packages to run it anyway");
+    }
+    eprint!("{message}. Continue? [y/N] ");
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+        anyhow::bail!("Aborted");
+    }
+    Ok(())
+}
+
+/// The token's current GraphQL budget, from a query that asks for nothing else (and so costs
+/// nothing). `None` without a token.
+async fn fetch_graphql_budget(github: &GitHubClient) -> Result<Option<GraphqlRateLimit>> {
+    let Some(GitHubToken { token, .. }) = github.token() else {
+        return Ok(None);
+    };
+    let resp: serde_json::Value = github
+        .authorize(github.http.post(&github.graphql_url), token)
+        .json(&serde_json::json!({ "query": "query { rateLimit { cost remaining resetAt } }" }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let rate_limit = resp
+        .pointer("/data/rateLimit")
+        .cloned()
+        .context("GitHub's response has no rateLimit")?;
+    Ok(Some(serde_json::from_value(rate_limit)?))
+}
+
+/// `--estimate`: what a scan of `to_fetch` would cost, without running it. Each repository is
+/// one GraphQL query of 1 point; README scans and retries come on top.
+fn print_estimate(
+    to_fetch: &[FetchTarget],
+    resumed: usize,
+    scan_readme: bool,
+    budget: Option<&GraphqlRateLimit>,
+    reserve: Option<u64>,
+) {
+    let github = to_fetch.iter().filter(|t| t.host == Host::Github).count();
+    let gitlab = to_fetch.len() - github;
+    println!("Repositories to query: {}", to_fetch.len());
+    println!("  GitHub: {github} (about {github} GraphQL points)");
+    if gitlab > 0 {
+        println!("  GitLab: {gitlab} (not limited by the GitHub budget)");
+    }
+    if resumed > 0 {
+        println!("  Already in the checkpoint: {resumed}");
+    }
+    if scan_readme {
+        println!(
+            "  --scan-readme: up to {github} more REST requests, for repositories without declared funding"
+        );
+    }
+    let Some(budget) = budget else {
+        println!("No GitHub token, so lookups would be unavailable and use no budget.");
+        return;
+    };
+    let usable = budget.remaining.saturating_sub(reserve.unwrap_or(0));
+    println!(
+        "GitHub budget: {} points left, resetting at {}",
+        budget.remaining, budget.reset_at
+    );
+    if github as u64 <= usable {
+        println!("The scan fits in the remaining budget.");
+    } else {
+        println!(
+            "The scan needs about {} more points than are available{}; it would pause until the reset with --rate-limit-reserve, or could be split with --checkpoint.",
+            github as u64 - usable,
+            if reserve.is_some() {
+                " after the reserve"
+            } else {
+                ""
+            }
+        );
+    }
+}
+
+/// Asks before opening more than this many browser tabs with `--open-all`.
+const OPEN_ALL_CONFIRM_ABOVE: usize = 5;
+
+/// Opens each result's first funding link for `--open-all`, pausing between them so the browser
+/// can keep up. Does nothing on machines without a display.
+async fn open_all(results: &[SponsorInfo]) -> Result<()> {
+    use std::io::{BufRead, IsTerminal, Write};
+
+    let mut links: Vec<&str> = Vec::new();
+    for link in results.iter().filter_map(|r| r.sponsor_links.first()) {
+        if !links.contains(&link.as_str()) {
+            links.push(link);
+        }
+    }
+    if links.is_empty() {
+        return Ok(());
+    }
+    if cfg!(all(unix, not(target_os = "macos")))
+        && std::env::var_os("DISPLAY").is_none()
+        && std::env::var_os("WAYLAND_DISPLAY").is_none()
+    {
+        eprintln!("Note: No display found, so --open-all is not opening any links");
+        return Ok(());
+    }
+    if links.len() > OPEN_ALL_CONFIRM_ABOVE {
+        if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+            eprintln!(
+                "Note: Not opening {} links without confirmation; run --open-all in a terminal",
+                links.len()
+            );
+            return Ok(());
+        }
+        eprint!("Open {} funding links in your browser? [y/N] ", links.len());
+        std::io::stderr().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().lock().read_line(&mut answer)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            return Ok(());
+        }
+    }
+    for (i, link) in links.iter().enumerate() {
+        if i > 0 {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        if let Err(e) = open::that_detached(link) {
+            warn!("Failed to open {link}: {e}");
+        }
+    }
+    Ok(())
+}
+
+/// The REST base and GraphQL endpoint for `host`. github.com is served from `api.github.com`,
+/// while GitHub Enterprise Server puts them under `/api/v3` and `/api/graphql`.
+fn api_urls(host: &str) -> (String, String) {
+    if let Ok(url) = std::env::var(GITHUB_API_URL_ENV) {
+        let url = url.trim_end_matches('/');
+        return (url.to_string(), format!("{url}/graphql"));
+    }
+    if host == "github.com" {
+        (
+            GITHUB_API_URL.to_string(),
+            format!("{GITHUB_API_URL}/graphql"),
+        )
+    } else {
+        (
+            format!("https://{host}/api/v3"),
+            format!("https://{host}/api/graphql"),
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+#[derive(Deserialize, PartialEq, Eq, Hash)]
+struct LockedPackage {
+    name: String,
+    version: String,
+}
+
+/// The `(name, version)` pairs in the workspace's Cargo.lock as of `git_ref`, for `--since`.
+fn locked_packages_at(workspace_root: &Path, git_ref: &str) -> Result<HashSet<LockedPackage>> {
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(workspace_root)
+        .args(["show", &format!("{git_ref}:./Cargo.lock")])
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Could not read Cargo.lock at {git_ref}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let text = String::from_utf8(output.stdout).context("Cargo.lock is not UTF-8")?;
+    let lockfile: Lockfile = toml::from_str(&text)
+        .with_context(|| format!("Failed to parse Cargo.lock at {git_ref}"))?;
+    Ok(lockfile.package.into_iter().collect())
+}
+
+/// Picks the workspace members to analyze the way cargo does: `-p` selects members by name (or
+/// `name@version`), `--workspace` takes every member, and otherwise `default-members` applies.
+fn select_roots<'a>(
+    metadata: &'a Metadata,
+    specs: &[String],
+    workspace: bool,
+) -> Result<Vec<&'a Package>> {
+    let members = metadata.workspace_packages();
+    if !specs.is_empty() {
+        return specs
+            .iter()
+            .map(|spec| {
+                let (name, version) = spec
+                    .split_once('@')
+                    .map_or((spec.as_str(), None), |(n, v)| (n, Some(v)));
+                members
+                    .iter()
+                    .copied()
+                    .find(|p| {
+                        p.name.as_str() == name
+                            && version.is_none_or(|v| p.version.to_string() == v)
+                    })
+                    .with_context(|| format!("Package `{spec}` is not a member of this workspace"))
+            })
+            .collect();
+    }
+    if workspace || !metadata.workspace_default_members.is_available() {
+        return Ok(members);
+    }
+    let defaults = metadata.workspace_default_packages();
+    Ok(if defaults.is_empty() {
+        members
+    } else {
+        defaults
+    })
+}
+
+/// Each dependency's position in the selected crates' manifests, in file order: `[dependencies]`,
+/// then `[build-dependencies]`, then `[dev-dependencies]`, then target-specific tables. Renamed
+/// dependencies are keyed by their real package name.
+fn declaration_order(roots: &[&Package]) -> HashMap<String, usize> {
+    fn add(order: &mut HashMap<String, usize>, table: Option<&toml::Value>) {
+        let Some(table) = table.and_then(toml::Value::as_table) else {
+            return;
+        };
+        for (key, spec) in table {
+            let name = spec
+                .get("package")
+                .and_then(toml::Value::as_str)
+                .unwrap_or(key);
+            let next = order.len();
+            order.entry(name.to_string()).or_insert(next);
+        }
+    }
+
+    let mut order = HashMap::new();
+    for root in roots {
+        let manifest = match std::fs::read_to_string(&root.manifest_path) {
+            Ok(text) => text,
+            Err(e) => {
+                debug!("Failed to read {}: {e}", root.manifest_path);
+                continue;
+            }
+        };
+        let manifest: toml::Table = match toml::from_str(&manifest) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                debug!("Failed to parse {}: {e}", root.manifest_path);
+                continue;
+            }
+        };
+        for section in ["dependencies", "build-dependencies", "dev-dependencies"] {
+            add(&mut order, manifest.get(section));
+        }
+        if let Some(targets) = manifest.get("target").and_then(toml::Value::as_table) {
+            for target in targets.values() {
+                for section in ["dependencies", "build-dependencies", "dev-dependencies"] {
+                    add(&mut order, target.get(section));
+                }
+            }
+        }
+    }
+    order
+}
+
+/// The package `--subtree` names, which must be reachable from the selected roots.
+fn select_subtree<'a>(
+    metadata: &'a Metadata,
+    reachable: Option<&HashSet<&PackageId>>,
+    spec: &str,
+) -> Result<&'a Package> {
+    let Some(reachable) = reachable else {
+        anyhow::bail!("--subtree needs cargo's resolved dependency graph");
+    };
+    let (name, version) = spec
+        .split_once('@')
+        .map_or((spec, None), |(n, v)| (n, Some(v)));
+    let matches: Vec<&Package> = metadata
+        .packages
+        .iter()
+        .filter(|p| {
+            reachable.contains(&p.id)
+                && p.name.as_str() == name
+                && version.is_none_or(|v| p.version.to_string() == v)
+        })
+        .collect();
+    match matches.as_slice() {
+        [] => anyhow::bail!("Package `{spec}` is not in the dependency graph"),
+        [one] => Ok(one),
+        several => anyhow::bail!(
+            "Package `{spec}` is ambiguous; pick a version: {}",
+            several
+                .iter()
+                .map(|p| format!("{}@{}", p.name, p.version))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Every package reachable from `roots` through the resolved graph, roots included. `None` when
+/// cargo didn't produce a resolve graph, in which case nothing can be narrowed down.
+fn reachable_packages<'a>(
+    metadata: &'a Metadata,
+    roots: &[&'a Package],
+) -> Option<HashSet<&'a PackageId>> {
+    let resolve = metadata.resolve.as_ref()?;
+    let nodes: HashMap<&PackageId, &cargo_metadata::Node> =
+        resolve.nodes.iter().map(|n| (&n.id, n)).collect();
+
+    let mut seen: HashSet<&PackageId> = HashSet::new();
+    let mut stack: Vec<&PackageId> = roots.iter().map(|p| &p.id).collect();
+    while let Some(id) = stack.pop() {
+        if !seen.insert(id) {
+            continue;
+        }
+        if let Some(node) = nodes.get(id) {
+            stack.extend(node.deps.iter().map(|d| &d.pkg));
+        }
+    }
+    Some(seen)
+}
+
+/// Classifies every package reachable from `roots` by the kinds of edge it is reached through.
+/// Anything below a build-dependency or a proc-macro only runs at compile time, so it's `Build`
+/// regardless of how it's declared there.
+fn dependency_kinds<'a>(
+    metadata: &'a Metadata,
+    roots: &[&'a Package],
+) -> HashMap<&'a PackageId, BTreeSet<DepKind>> {
+    use cargo_metadata::DependencyKind;
+
+    let mut kinds: HashMap<&PackageId, BTreeSet<DepKind>> = HashMap::new();
+    let Some(resolve) = &metadata.resolve else {
+        return kinds;
+    };
+    let nodes: HashMap<&PackageId, &cargo_metadata::Node> =
+        resolve.nodes.iter().map(|n| (&n.id, n)).collect();
+    let packages: HashMap<&PackageId, &Package> =
+        metadata.packages.iter().map(|p| (&p.id, p)).collect();
+    let is_proc_macro = |id: &PackageId| {
+        packages
+            .get(id)
+            .is_some_and(|p| p.targets.iter().any(|t| t.is_proc_macro()))
+    };
+
+    let mut stack: Vec<(&PackageId, Option<DepKind>)> =
+        roots.iter().map(|p| (&p.id, None)).collect();
+    while let Some((id, inherited)) = stack.pop() {
+        let Some(node) = nodes.get(id) else {
+            continue;
+        };
+        for dep in &node.deps {
+            for info in &dep.dep_kinds {
+                let kind = match (info.kind, inherited) {
+                    (DependencyKind::Build, _) | (_, Some(DepKind::Build)) => DepKind::Build,
+                    // Members' own dev-dependencies; cargo never builds a dependency's.
+                    (DependencyKind::Development, None) => DepKind::Dev,
+                    (DependencyKind::Development, Some(_)) => continue,
+                    (_, inherited) => inherited.unwrap_or(DepKind::Normal),
+                };
+                let kind = if is_proc_macro(&dep.pkg) {
+                    DepKind::Build
+                } else {
+                    kind
+                };
+                if kinds.entry(&dep.pkg).or_default().insert(kind) {
+                    stack.push((&dep.pkg, Some(kind)));
+                }
+            }
+        }
+    }
+    kinds
+}
+
+/// The URL used to find a package's repository: its `repository` field, then the git URL it was
+/// fetched from for git dependencies, or with `--use-homepage-fallback`, a `homepage` that points
+/// at a repository.
+fn package_repo_url(package: &Package, use_homepage: bool) -> Option<&str> {
+    if let Some(repository) = package.repository.as_deref() {
+        return Some(repository);
+    }
+    if let Some(git_url) = package
+        .source
+        .as_ref()
+        .and_then(|s| git_source_url(&s.repr))
+    {
+        return Some(git_url);
+    }
+    package
+        .homepage
+        .as_deref()
+        .filter(|homepage| use_homepage && extract_repo(homepage).is_some())
+}
+
+/// The repository URL inside a git source such as `git+https://github.com/o/r?branch=b#rev`.
+fn git_source_url(source: &str) -> Option<&str> {
+    let url = source.strip_prefix("git+")?;
+    url.split(['?', '#']).next()
+}
+
+/// Counts, for each GitHub repository, how many distinct packages outside that repository depend
+/// on one of its crates. Edges come from the resolved graph, so this is the in-degree of the repo.
+fn count_dependents(metadata: &Metadata, use_homepage: bool) -> HashMap<(String, String), usize> {
+    let Some(resolve) = &metadata.resolve else {
+        return HashMap::new();
+    };
+    let repo_of: HashMap<&PackageId, (String, String)> = metadata
+        .packages
+        .iter()
+        .filter_map(|p| {
+            let (_, owner, repo) = extract_repo(package_repo_url(p, use_homepage)?)?;
+            Some((&p.id, repo_key(&owner, &repo)))
+        })
+        .collect();
+
+    let mut dependents: HashMap<(String, String), HashSet<&PackageId>> = HashMap::new();
+    for node in &resolve.nodes {
+        let own_repo = repo_of.get(&node.id);
+        for dep in &node.deps {
+            if let Some(dep_repo) = repo_of.get(&dep.pkg)
+                && own_repo != Some(dep_repo)
+            {
+                dependents
+                    .entry(dep_repo.clone())
+                    .or_default()
+                    .insert(&node.id);
+            }
+        }
+    }
+    dependents
+        .into_iter()
+        .map(|(repo, set)| (repo, set.len()))
+        .collect()
+}
+
+/// Funding links a crate declares in its own manifest:
+///
+/// ```toml
+/// [package.metadata]
+/// funding = "https://github.com/sponsors/me"   # or a list of URLs
+/// ```
+///
+/// Anything that isn't an http(s) URL is ignored.
+fn manifest_funding_links(package: &Package) -> Vec<String> {
+    let links = match package.metadata.get("funding") {
+        Some(serde_json::Value::String(link)) => vec![link.as_str()],
+        Some(serde_json::Value::Array(links)) => {
+            links.iter().filter_map(serde_json::Value::as_str).collect()
+        }
+        _ => return Vec::new(),
+    };
+    let mut valid = Vec::new();
+    for link in links {
+        let link = link.trim();
+        if Url::parse(link).is_ok_and(|u| matches!(u.scheme(), "http" | "https")) {
+            if !valid.iter().any(|l| l == link) {
+                valid.push(link.to_string());
+            }
+        } else {
+            debug!(package = %package.name, link, "ignoring invalid package.metadata.funding entry");
+        }
+    }
+    valid
+}
+
+/// Splits the dependencies into repositories to look up and, for crates whose repository we
+/// can't query, results built from `package.metadata.funding` alone.
+fn collect_repos_to_fetch(
+    deps: &[&Package],
+    dependents: &HashMap<(String, String), usize>,
+    use_homepage: bool,
+    hosts: &[Host],
+    exclude_repo_urls: &[Regex],
+    ignore: &IgnoreList,
+    rows_per: RowsPer,
+) -> (Vec<FetchTarget>, Vec<SponsorInfo>) {
+    let mut seen_repos: HashMap<(String, String), usize> = HashMap::new();
+    let mut to_fetch: Vec<FetchTarget> = Vec::new();
+    let mut manifest_only = Vec::new();
+
+    for package in deps {
+        let repo_url = package_repo_url(package, use_homepage);
+        if let Some(repo_url) = repo_url
+            && exclude_repo_urls.iter().any(|re| re.is_match(repo_url))
+        {
+            debug!(package = %package.name, repo_url, "excluded by --exclude-repo-url");
+            continue;
+        }
+        if let Some(entry) = ignore.matching_entry(&package.name, repo_url.unwrap_or_default()) {
+            debug!(package = %package.name, entry, "excluded by {IGNORE_FILE}");
+            continue;
+        }
+        let manifest_links = manifest_funding_links(package);
+        let version = (rows_per == RowsPer::Version).then(|| package.version.to_string());
+
+        let Some((repo_url, host, repo_owner, repo_name)) = repo_url
+            .and_then(|url| extract_repo(url).map(|(host, owner, repo)| (url, host, owner, repo)))
+            .filter(|(_, host, _, _)| hosts.contains(host))
+        else {
+            if let Some(url) = repo_url
+                && Url::parse(url).is_ok_and(|u| u.host_str() == Some("gist.github.com"))
+            {
+                debug!(package = %package.name, repo_url = url, "repository is a gist; nothing to look up");
+            }
+            if !manifest_links.is_empty() {
+                manifest_only.push(SponsorInfo {
+                    name: package.name.to_string(),
+                    version,
+                    license: package.license.clone(),
+                    repository: repo_url.unwrap_or_default().to_string(),
+                    sponsor_links: manifest_links,
+                    sponsor_count: None,
+                    dependents: 0,
+                    last_activity: None,
+                    topics: Vec::new(),
+                    min_tier: None,
+                    source: FundingSource::Manifest,
+                    dep_kinds: Vec::new(),
+                    homepage: None,
+                    documentation: None,
+                    owner_type: OwnerType::Other,
+                    packages: Vec::new(),
+                    extra: serde_json::Map::new(),
+                });
+            }
+            continue;
+        };
+
+        let key = repo_key(&repo_owner, &repo_name);
+        if let Some(&index) = seen_repos.get(&key) {
+            let target = &mut to_fetch[index];
+            let already_listed = |name: &str, other: &Option<String>| {
+                name == package.name.as_str() && *other == version
+            };
+            if rows_per != RowsPer::Repository
+                && !already_listed(&target.pkg_name, &target.version)
+                && !target
+                    .shared_with
+                    .iter()
+                    .any(|s| already_listed(&s.name, &s.version))
+            {
+                target.shared_with.push(SharedPackage {
+                    name: package.name.to_string(),
+                    version,
+                    license: package.license.clone(),
+                });
+            }
+            for link in manifest_links {
+                if !target.manifest_links.contains(&link) {
+                    target.manifest_links.push(link);
+                }
+            }
+            continue;
+        }
+        let repo_dependents = dependents.get(&key).copied().unwrap_or(0);
+        seen_repos.insert(key, to_fetch.len());
+        to_fetch.push(FetchTarget {
+            pkg_name: package.name.to_string(),
+            version,
+            license: package.license.clone(),
+            repo_url: repo_url.to_string(),
+            host,
+            dependents: repo_dependents,
+            owner: repo_owner,
+            repo: repo_name,
+            manifest_links,
+            shared_with: Vec::new(),
+        });
+    }
+
+    (to_fetch, manifest_only)
+}
+
+/// The funding score: the percentage of checked repositories that are sponsorable, where each
+/// repository weighs `1 + dependents`, so widely shared ones count for more. Repositories whose
+/// lookup failed or was unavailable are left out. `None` when nothing could be checked.
+fn funding_score(
+    outcome: &FetchOutcome,
+    dependents: &HashMap<(String, String), usize>,
+) -> Option<f64> {
+    let mut funded = 0usize;
+    let mut checked = 0usize;
+    for (key, status) in &outcome.statuses {
+        let weight = 1 + dependents.get(key).copied().unwrap_or(0);
+        match status {
+            RepoStatus::Funded(_) => {
+                funded += weight;
+                checked += weight;
+            }
+            RepoStatus::NoFunding => checked += weight,
+            RepoStatus::Unavailable | RepoStatus::Failed(..) => {}
+        }
+    }
+    (checked > 0).then(|| 100.0 * funded as f64 / checked as f64)
+}
+
+/// The `--export-anon` summary: counts and percentages only, so it can be shared without revealing
+/// which dependencies a project has. Written to a local file; nothing is sent anywhere.
+fn render_anon_summary(outcome: &FetchOutcome, score: Option<f64>) -> Result<String> {
+    let mut statuses: BTreeMap<&str, usize> = BTreeMap::new();
+    for status in outcome.statuses.values() {
+        let label = match status {
+            RepoStatus::Funded(_) => "funded",
+            RepoStatus::NoFunding => "no_funding",
+            RepoStatus::Unavailable => "unavailable",
+            RepoStatus::Failed(..) => "failed",
+        };
+        *statuses.entry(label).or_default() += 1;
+    }
+    let funded = statuses.get("funded").copied().unwrap_or(0);
+    let checked = funded + statuses.get("no_funding").copied().unwrap_or(0);
+
+    // A project offering several platforms counts once towards each.
+    let mut platforms: BTreeMap<&str, usize> = BTreeMap::new();
+    for info in &outcome.results {
+        let distinct: HashSet<Platform> = info
+            .sponsor_links
+            .iter()
+            .map(|l| Platform::classify(l))
+            .collect();
+        for platform in distinct {
+            *platforms.entry(platform.label()).or_default() += 1;
+        }
+    }
+
+    let summary = serde_json::json!({
+        "tool_version": env!("CARGO_PKG_VERSION"),
+        "repositories": statuses,
+        "coverage_percent": (checked > 0).then(|| 100.0 * funded as f64 / checked as f64),
+        "funding_score": score,
+        "platforms": platforms,
+    });
+    Ok(serde_json::to_string_pretty(&summary)?)
+}
+
+/// Reads a `--fixture` file: a JSON object whose keys are `owner/repo` (GitLab projects as
+/// `gitlab.com/group/project`) and whose values are [`RepoInfo`]s. Repositories missing from it
+/// are reported as unavailable.
+fn load_fixture(path: &Path) -> Result<HashMap<(String, String), RepoInfo>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let entries: HashMap<String, RepoInfo> = serde_json::from_str(&text)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    entries
+        .into_iter()
+        .map(|(key, info)| {
+            let (owner, repo) = key
+                .rsplit_once('/')
+                .with_context(|| format!("{}: key {key:?} is not owner/repo", path.display()))?;
+            Ok((repo_key(owner, repo), info))
+        })
+        .collect()
+}
+
+/// One finished lookup in a `--checkpoint` file. Only repositories that were actually queried
+/// are recorded; failures and unavailable ones (say, for lack of a token) are retried on resume.
+#[derive(Deserialize)]
+struct CheckpointEntry {
+    owner: String,
+    repo: String,
+    info: RepoInfo,
+}
+
+/// An append-only `--checkpoint` file, written as each lookup completes so an interrupted scan
+/// loses nothing.
+struct Checkpoint {
+    path: PathBuf,
+    /// `None` when only read, as by `--estimate`, which must not create the file.
+    file: Option<std::fs::File>,
+    /// Lookups already in the file, keyed by [`repo_key`].
+    done: HashMap<(String, String), RepoInfo>,
+}
+
+impl Checkpoint {
+    /// Opens `path` for appending after loading the lookups it already holds.
+    fn open(path: &Path) -> Result<Self> {
+        let mut checkpoint = Self::read(path)?;
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        checkpoint.file = Some(file);
+        Ok(checkpoint)
+    }
+
+    /// Loads the lookups `path` holds, if it exists, without opening it for writing. A truncated
+    /// last line, as left by a crash mid-write, is skipped.
+    fn read(path: &Path) -> Result<Self> {
+        let mut done = HashMap::new();
+        match std::fs::read_to_string(path) {
+            Ok(text) => {
+                for line in text.lines().filter(|l| !l.trim().is_empty()) {
+                    match serde_json::from_str::<CheckpointEntry>(line) {
+                        Ok(entry) => {
+                            done.insert(repo_key(&entry.owner, &entry.repo), entry.info);
+                        }
+                        Err(e) => warn!("Skipping unreadable line in {}: {e}", path.display()),
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+            }
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: None,
+            done,
+        })
+    }
+
+    /// The recorded lookup for `target`, if an earlier run finished it.
+    fn take_finished(&mut self, target: &FetchTarget) -> Option<RepoInfo> {
+        self.done.remove(&repo_key(&target.owner, &target.repo))
+    }
+
+    fn record(&mut self, target: &FetchTarget, info: &RepoInfo) {
+        use std::io::Write;
+
+        let Some(file) = &mut self.file else {
+            return;
+        };
+        let entry = serde_json::json!({ "owner": target.owner, "repo": target.repo, "info": info });
+        if let Err(e) = writeln!(file, "{entry}") {
+            warn!("Failed to write {}: {e}", self.path.display());
+        }
+    }
+}
+
+fn process_result(
+    outcome: &mut FetchOutcome,
+    target: FetchTarget,
+    result: Result<Option<RepoInfo>>,
+) {
+    let FetchTarget {
+        pkg_name,
+        version,
+        license,
+        repo_url,
+        host: _,
+        owner,
+        repo,
+        dependents,
+        manifest_links,
+        shared_with,
+    } = target;
+    let result = match result {
+        Ok(Some(mut info)) => {
+            if info.funding_links.is_empty() && !manifest_links.is_empty() {
+                info.source = FundingSource::Manifest;
+            }
+            for link in manifest_links {
+                if !info.funding_links.contains(&link) {
+                    info.funding_links.push(link);
+                }
+            }
+            Ok(Some(info))
+        }
+        // Without a lookup the crate's own manifest is still good enough.
+        Ok(None) if !manifest_links.is_empty() => Ok(Some(RepoInfo {
+            funding_links: manifest_links,
+            sponsor_count: None,
+            last_activity: None,
+            topics: Vec::new(),
+            min_tier: None,
+            source: FundingSource::Manifest,
+            owner_type: OwnerType::Other,
+        })),
+        other => other,
+    };
+    let status = match result {
+        Ok(Some(info)) if !info.funding_links.is_empty() => {
+            let row = SponsorInfo {
+                name: pkg_name,
+                version,
+                license,
+                repository: repo_url,
+                sponsor_links: info.funding_links.clone(),
+                sponsor_count: info.sponsor_count,
+                dependents,
+                last_activity: info.last_activity.clone(),
+                topics: info.topics.clone(),
+                min_tier: info.min_tier.clone(),
+                source: info.source,
+                dep_kinds: Vec::new(),
+                homepage: None,
+                documentation: None,
+                owner_type: info.owner_type,
+                packages: Vec::new(),
+                extra: serde_json::Map::new(),
+            };
+            // The repository was looked up once; its answer applies to each of its packages.
+            let shared: Vec<SponsorInfo> = shared_with
+                .into_iter()
+                .map(|shared| SponsorInfo {
+                    name: shared.name,
+                    version: shared.version,
+                    license: shared.license,
+                    ..row.clone()
+                })
+                .collect();
+            outcome.results.push(row);
+            outcome.results.extend(shared);
+            RepoStatus::Funded(info)
+        }
+        Ok(Some(_)) => {
+            outcome.unfunded.push(UnfundedRepo {
+                name: pkg_name,
+                repository: repo_url,
+                dependents,
+            });
+            RepoStatus::NoFunding
+        }
+        Ok(None) => RepoStatus::Unavailable,
+        Err(e) => {
+            let kind = FailureKind::of(&e);
+            warn!(reason = ?kind, "Failed to fetch sponsor info for {owner}/{repo}: {e}");
+            RepoStatus::Failed(kind, e.to_string())
+        }
+    };
+    outcome.statuses.insert(repo_key(&owner, &repo), status);
+}
+
+/// Prints the `--show-unfunded-popular` list: where the dependency tree leans on projects that
+/// offer no way to fund them.
+fn print_unfunded(unfunded: &[UnfundedRepo], omitted: usize) {
+    if unfunded.is_empty() {
+        println!("{}", "No checked repository is without funding.".green());
+        return;
+    }
+    println!(
+        "{}",
+        "📉 Widely Used Projects Without Funding".bold().cyan()
+    );
+    println!();
+    let name_width = unfunded
+        .iter()
+        .map(|u| u.name.len())
+        .max()
+        .unwrap_or(7)
+        .max(7);
+    println!(
+        "{}  {}  {}",
+        format!("{:<name_width$}", "Package").bold(),
+        format!("{:<8}", "Used by").bold(),
+        "Repository".bold()
+    );
+    println!(
+        "{}  {}  {}",
+        "─".repeat(name_width),
+        "─".repeat(8),
+        "─".repeat(40)
+    );
+    for repo in unfunded {
+        println!(
+            "{}  {:<8}  {}",
+            format!("{:<name_width$}", repo.name).yellow(),
+            repo.dependents,
+            repo.repository.dimmed()
+        );
+    }
+    if omitted > 0 {
+        println!();
+        println!(
+            "{}",
+            format!("({omitted} more omitted by --limit)").dimmed()
+        );
+    }
+}
+
+/// A funding link and everything in the report it would support, for `--top-destinations`.
+#[derive(Serialize)]
+struct FundingDestination {
+    link: String,
+    platform: String,
+    /// Distinct repositories listing the link.
+    repositories: usize,
+    packages: Vec<String>,
+    /// Summed over those repositories.
+    dependents: usize,
+}
+
+/// Groups results by funding link, the ones covering the most repositories first. Links differing
+/// only by case or a trailing slash count as one.
+fn top_destinations(results: &[SponsorInfo]) -> Vec<FundingDestination> {
+    let mut destinations: Vec<FundingDestination> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut repositories: Vec<HashSet<String>> = Vec::new();
+    for info in results {
+        let repository = merge_key(info);
+        let names = if info.packages.is_empty() {
+            std::slice::from_ref(&info.name)
+        } else {
+            info.packages.as_slice()
+        };
+        for link in &info.sponsor_links {
+            let key = link.trim_end_matches('/').to_lowercase();
+            let i = *index.entry(key).or_insert_with(|| {
+                destinations.push(FundingDestination {
+                    link: link.clone(),
+                    platform: platform_label(link),
+                    repositories: 0,
+                    packages: Vec::new(),
+                    dependents: 0,
+                });
+                repositories.push(HashSet::new());
+                destinations.len() - 1
+            });
+            let destination = &mut destinations[i];
+            if repositories[i].insert(repository.clone()) {
+                destination.repositories += 1;
+                destination.dependents += info.dependents;
+            }
+            for name in names {
+                if !destination.packages.contains(name) {
+                    destination.packages.push(name.clone());
+                }
+            }
+        }
+    }
+    destinations.sort_by(|a, b| {
+        b.repositories
+            .cmp(&a.repositories)
+            .then_with(|| b.packages.len().cmp(&a.packages.len()))
+            .then_with(|| a.link.cmp(&b.link))
+    });
+    destinations
+}
+
+fn print_top_destinations(destinations: &[FundingDestination], omitted: usize) {
+    let Some(top) = destinations.first() else {
+        println!("{}", "No sponsorable dependencies found.".yellow());
+        return;
+    };
+    println!("{}", "🎯 Top Funding Destinations".bold().cyan());
+    println!();
+    println!(
+        "{} {} {}",
+        "Sponsoring".dimmed(),
+        top.link.blue().underline(),
+        format!(
+            "supports {} of your crates ({} {}).",
+            top.packages.len(),
+            top.repositories,
+            if top.repositories == 1 {
+                "repository"
+            } else {
+                "repositories"
+            }
+        )
+        .dimmed()
+    );
+    println!();
+    let platform_width = destinations
+        .iter()
+        .map(|d| d.platform.len())
+        .max()
+        .unwrap_or(8)
+        .max(8);
     println!(
-        "  {:<name_width$}  {:<sponsors_width$}  {}",
-        "─".repeat(name_width),
-        "─".repeat(sponsors_width),
-        "─".repeat(40),
+        "{}  {}  {}  {}",
+        format!("{:<5}", "Repos").bold(),
+        format!("{:<6}", "Crates").bold(),
+        format!("{:<platform_width$}", "Platform").bold(),
+        "Link".bold()
+    );
+    println!(
+        "{}  {}  {}  {}",
+        "─".repeat(5),
+        "─".repeat(6),
+        "─".repeat(platform_width),
+        "─".repeat(40)
+    );
+    for destination in destinations {
+        println!(
+            "{:<5}  {:<6}  {}  {}",
+            destination.repositories,
+            destination.packages.len(),
+            format!("{:<platform_width$}", destination.platform).green(),
+            destination.link.blue().underline()
+        );
+    }
+    if omitted > 0 {
+        println!();
+        println!(
+            "{}",
+            format!("({omitted} more omitted by --limit)").dimmed()
+        );
+    }
+}
+
+/// `--top-destinations`, in place of the report.
+fn report_top_destinations(results: &[SponsorInfo], args: &Args) -> Result<()> {
+    let mut destinations = top_destinations(results);
+    let limit = args.limit.unwrap_or(usize::MAX);
+    let omitted = destinations.len().saturating_sub(limit);
+    destinations.truncate(limit);
+    match args.output {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&destinations)?),
+        _ => print_top_destinations(&destinations, omitted),
+    }
+    Ok(())
+}
+
+/// Prints, for `--explain`, each filter a package went through on its way into (or out of) the
+/// results. Mirrors the checks in `main` and [`collect_repos_to_fetch`].
+fn explain_package(
+    args: &Args,
+    metadata: &Metadata,
+    reachable: Option<&HashSet<&PackageId>>,
+    direct_deps: &HashSet<String>,
+    deps: &[&Package],
+    ignore: &IgnoreList,
+    outcome: &FetchOutcome,
+) {
+    let Some(name) = args.explain.as_deref() else {
+        return;
+    };
+    let candidates: Vec<&Package> = metadata
+        .packages
+        .iter()
+        .filter(|p| p.name.as_str() == name)
+        .collect();
+    if candidates.is_empty() {
+        eprintln!("{name}: not in the dependency graph");
+        return;
+    }
+
+    let pass = |ok: bool, text: &str| {
+        let mark = if ok {
+            "✓".green().to_string()
+        } else {
+            "✗".red().to_string()
+        };
+        eprintln!("  {mark} {text}");
+    };
+
+    for package in candidates {
+        eprintln!("{} {}:", package.name.bold(), package.version);
+        if metadata.workspace_members.contains(&package.id) {
+            pass(
+                args.include_self,
+                "workspace member (only listed with --include-self)",
+            );
+            if !args.include_self {
+                continue;
+            }
+        }
+        let is_reachable = reachable.is_none_or(|r| r.contains(&package.id));
+        pass(is_reachable, "depended on by the selected packages");
+        if !is_reachable {
+            continue;
+        }
+        if args.top_level_only && !metadata.workspace_members.contains(&package.id) {
+            let direct = direct_deps.contains(package.name.as_str());
+            pass(direct, "direct dependency (--top-level-only)");
+            if !direct {
+                continue;
+            }
+        }
+        let Some(repo_url) = package_repo_url(package, args.use_homepage_fallback) else {
+            pass(false, "has a repository URL");
+            continue;
+        };
+        pass(true, &format!("has a repository URL: {repo_url}"));
+        if let Some(re) = args
+            .exclude_repo_url
+            .iter()
+            .find(|re| re.is_match(repo_url))
+        {
+            pass(
+                false,
+                &format!("not excluded (matches --exclude-repo-url {re})"),
+            );
+            continue;
+        }
+        if let Some(entry) = ignore.matching_entry(&package.name, repo_url) {
+            pass(
+                false,
+                &format!("not excluded (matches `{entry}` in {IGNORE_FILE})"),
+            );
+            continue;
+        }
+        let Some((host, owner, repo)) = extract_repo(repo_url) else {
+            pass(false, "repository is on GitHub or GitLab");
+            continue;
+        };
+        pass(
+            true,
+            &format!("repository is on a supported host: {owner}/{repo}"),
+        );
+        if !args.hosts.contains(&host) {
+            pass(false, &format!("{host:?} is among --hosts"));
+            continue;
+        }
+        let key = repo_key(&owner, &repo);
+        let first_claim = deps.iter().find(|p| {
+            package_repo_url(p, args.use_homepage_fallback)
+                .filter(|url| !args.exclude_repo_url.iter().any(|re| re.is_match(url)))
+                .filter(|url| ignore.matching_entry(&p.name, url).is_none())
+                .and_then(extract_repo)
+                .is_some_and(|(_, owner, repo)| repo_key(&owner, &repo) == key)
+        });
+        if let Some(first) = first_claim
+            && first.name != package.name
+        {
+            pass(
+                false,
+                &format!(
+                    "first package for its repository (listed as {})",
+                    first.name
+                ),
+            );
+            continue;
+        }
+        match outcome.statuses.get(&key) {
+            Some(RepoStatus::Funded(info)) => pass(
+                true,
+                &format!("declares funding: {}", info.funding_links.join(", ")),
+            ),
+            Some(RepoStatus::NoFunding) => pass(false, "declares funding"),
+            Some(RepoStatus::Unavailable) => pass(
+                false,
+                "repository could be queried (not found, or no token)",
+            ),
+            Some(RepoStatus::Failed(kind, e)) => {
+                pass(false, &format!("lookup succeeded ({}): {e}", kind.label()));
+            }
+            None => pass(false, "repository was checked before the scan stopped"),
+        }
+        if matches!(outcome.statuses.get(&key), Some(RepoStatus::Funded(_))) {
+            let shown = outcome
+                .results
+                .iter()
+                .any(|r| r.name == package.name.as_str());
+            pass(
+                shown,
+                "kept by --active-within, --prefer-personal and --limit",
+            );
+        }
+    }
+}
+
+/// Builds the `--full` report, mapping each package onto the status of the repository it points at.
+fn build_full_report(
+    deps: &[&Package],
+    outcome: &FetchOutcome,
+    use_homepage: bool,
+) -> Vec<PackageReport> {
+    deps.iter()
+        .map(|package| {
+            let repo_url = package_repo_url(package, use_homepage);
+            let mut report = PackageReport {
+                name: package.name.to_string(),
+                version: package.version.to_string(),
+                license: package.license.clone(),
+                repository: repo_url.map(str::to_string),
+                owner: None,
+                repo: None,
+                status: PackageStatus::NoRepository,
+                sponsor_links: Vec::new(),
+                sponsor_count: None,
+                error: None,
+                error_kind: None,
+            };
+            let Some(repo_url) = repo_url else {
+                return report;
+            };
+            let Some((_, owner, repo)) = extract_repo(repo_url) else {
+                report.status = match Url::parse(repo_url) {
+                    Err(_) => PackageStatus::InvalidUrl,
+                    Ok(url) if !is_github_host(&url) && url.host_str() != Some("gitlab.com") => {
+                        PackageStatus::NotGithub
+                    }
+                    Ok(_) => PackageStatus::NotARepository,
+                };
+                return report;
+            };
+            report.status = match outcome.statuses.get(&repo_key(&owner, &repo)) {
+                Some(RepoStatus::Funded(info)) => {
+                    report.sponsor_links.clone_from(&info.funding_links);
+                    report.sponsor_count = info.sponsor_count;
+                    PackageStatus::Sponsorable
+                }
+                Some(RepoStatus::NoFunding) => PackageStatus::NoFunding,
+                Some(RepoStatus::Unavailable) => PackageStatus::Unavailable,
+                Some(RepoStatus::Failed(kind, e)) => {
+                    report.error = Some(e.clone());
+                    report.error_kind = Some(*kind);
+                    PackageStatus::FetchFailed
+                }
+                None => PackageStatus::NotChecked,
+            };
+            report.owner = Some(owner);
+            report.repo = Some(repo);
+            report
+        })
+        .collect()
+}
+
+/// Whether the project was pushed to after `cutoff`. `None` means the activity date is unknown.
+fn is_active_since(info: &SponsorInfo, cutoff: SystemTime) -> Option<bool> {
+    let pushed_at = humantime::parse_rfc3339(info.last_activity.as_deref()?).ok()?;
+    Some(pushed_at >= cutoff)
+}
+
+/// The sponsor count used for sorting and `--min-sponsors`. With `--treat-unknown-as-zero` an owner
+/// without a listing counts as 0; otherwise it stays unknown (and sorts below 0).
+fn comparable_sponsor_count(info: &SponsorInfo, unknown_as_zero: bool) -> Option<u32> {
+    if unknown_as_zero {
+        Some(info.sponsor_count.unwrap_or(0))
+    } else {
+        info.sponsor_count
+    }
+}
+
+fn sort_results(results: &mut [SponsorInfo], key: SortKey, unknown_as_zero: bool, reverse: bool) {
+    match key {
+        SortKey::Name => results.sort_by(|a, b| a.name.cmp(&b.name)),
+        SortKey::Sponsors => results.sort_by(|a, b| {
+            comparable_sponsor_count(b, unknown_as_zero)
+                .cmp(&comparable_sponsor_count(a, unknown_as_zero))
+                .then_with(|| a.name.cmp(&b.name))
+        }),
+        SortKey::Dependents => results.sort_by(|a, b| {
+            b.dependents
+                .cmp(&a.dependents)
+                .then_with(|| a.name.cmp(&b.name))
+        }),
+    }
+    if reverse {
+        results.reverse();
+    }
+}
+
+/// Display options for the rich table.
+#[derive(Clone, Copy)]
+struct TableStyle<'a> {
+    /// `--humanize`
+    humanize: bool,
+    /// `--show-all-links`
+    show_all_links: bool,
+    /// `--columns`, in display order.
+    columns: &'a [Column],
+}
+
+/// A field `--columns` can show in the rich table and CSV output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Column {
+    Name,
+    Sponsors,
+    #[value(alias = "dependents")]
+    UsedBy,
+    Kind,
+    License,
+    Platform,
+    Link,
+    Repository,
+    LastActive,
+    OwnerType,
+    Topics,
+    Homepage,
+    #[value(alias = "docs")]
+    Documentation,
+}
+
+const DEFAULT_TABLE_COLUMNS: &[Column] = &[
+    Column::Name,
+    Column::Sponsors,
+    Column::UsedBy,
+    Column::Kind,
+    Column::License,
+    Column::Platform,
+    Column::Link,
+];
+
+/// The default table plus the package's own links, for `--show-links`.
+const SHOW_LINKS_COLUMNS: &[Column] = &[
+    Column::Name,
+    Column::Sponsors,
+    Column::UsedBy,
+    Column::Kind,
+    Column::License,
+    Column::Documentation,
+    Column::Homepage,
+    Column::Platform,
+    Column::Link,
+];
+
+const DEFAULT_CSV_COLUMNS: &[Column] = &[
+    Column::Name,
+    Column::License,
+    Column::Sponsors,
+    Column::UsedBy,
+    Column::Platform,
+    Column::Link,
+];
+
+impl Column {
+    fn header(self) -> &'static str {
+        match self {
+            Self::Name => "Package",
+            // GitHub only counts sponsors per account, not per repository.
+            Self::Sponsors => "Owner sponsors",
+            Self::UsedBy => "Used by",
+            Self::Kind => "Kind",
+            Self::License => "License",
+            Self::Platform => "Platform",
+            Self::Link => "Link",
+            Self::Repository => "Repository",
+            Self::LastActive => "Last active",
+            Self::OwnerType => "Owner",
+            Self::Topics => "Topics",
+            Self::Homepage => "Homepage",
+            Self::Documentation => "Docs",
+        }
+    }
+
+    /// The CSV header, in the snake case of the JSON fields.
+    fn csv_header(self) -> &'static str {
+        match self {
+            Self::Name => "package",
+            Self::Sponsors => "owner_sponsors",
+            Self::UsedBy => "used_by",
+            Self::Kind => "kind",
+            Self::License => "license",
+            Self::Platform => "platform",
+            Self::Link => "link",
+            Self::Repository => "repository",
+            Self::LastActive => "last_active",
+            Self::OwnerType => "owner_type",
+            Self::Topics => "topics",
+            Self::Homepage => "homepage",
+            Self::Documentation => "documentation",
+        }
+    }
+
+    /// Columns keep this width even when every value is shorter, so tables line up across
+    /// sections.
+    fn min_width(self) -> usize {
+        match self {
+            Self::Name => 10,
+            Self::Sponsors => 14,
+            Self::UsedBy => 8,
+            Self::Platform => 15,
+            Self::LastActive => 11,
+            other => other.header().len(),
+        }
+    }
+
+    /// The rich table cell, before padding and color.
+    fn cell(self, info: &SponsorInfo, style: TableStyle) -> String {
+        match self {
+            Self::Sponsors => sponsors_cell(info, style.humanize),
+            Self::Kind => dep_kinds_cell(info),
+            Self::Platform => primary_platform(info),
+            _ => self
+                .value(info)
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| "-".to_string()),
+        }
+    }
+
+    /// The raw value, as written to CSV.
+    fn value(self, info: &SponsorInfo) -> Option<String> {
+        match self {
+            Self::Name => Some(info.display_name()),
+            Self::Sponsors => info.sponsor_count.map(|c| c.to_string()),
+            Self::UsedBy => Some(info.dependents.to_string()),
+            Self::Kind => Some(
+                info.dep_kinds
+                    .iter()
+                    .map(|k| k.label())
+                    .collect::<Vec<_>>()
+                    .join("+"),
+            ),
+            Self::License => info.license.clone(),
+            Self::Platform => Some(primary_platform(info)),
+            Self::Link => info.sponsor_links.first().cloned(),
+            Self::Repository => Some(info.repository.clone()),
+            // Just the date of the RFC 3339 timestamp.
+            Self::LastActive => info
+                .last_activity
+                .as_deref()
+                .map(|t| t.get(..10).unwrap_or(t).to_string()),
+            Self::OwnerType => match info.owner_type {
+                OwnerType::User => Some("user".to_string()),
+                OwnerType::Organization => Some("organization".to_string()),
+                OwnerType::Other => None,
+            },
+            Self::Topics => Some(info.topics.join(", ")),
+            Self::Homepage => info.homepage.clone(),
+            Self::Documentation => info.documentation.clone(),
+        }
+    }
+
+    fn paint(self, text: &str) -> String {
+        match self {
+            Self::Name => text.yellow().to_string(),
+            Self::Sponsors | Self::UsedBy | Self::Kind | Self::Repository => {
+                text.dimmed().to_string()
+            }
+            Self::Platform => text.green().to_string(),
+            Self::Homepage | Self::Documentation => text.underline().to_string(),
+            Self::Link => text.blue().underline().to_string(),
+            _ => text.to_string(),
+        }
+    }
+}
+
+fn print_results(results: &[SponsorInfo], style: TableStyle) {
+    print_grouped_results(
+        results.len(),
+        &[(String::new(), results.iter().collect())],
+        style,
+    );
+}
+
+/// Groups results under each of their topics, largest topic first. A project can appear in
+/// several groups; ones without topics come last.
+fn group_by_topic(results: &[SponsorInfo]) -> Vec<(String, Vec<&SponsorInfo>)> {
+    let mut by_topic: HashMap<&str, Vec<&SponsorInfo>> = HashMap::new();
+    let mut untagged = Vec::new();
+    for info in results {
+        if info.topics.is_empty() {
+            untagged.push(info);
+        }
+        for topic in &info.topics {
+            by_topic.entry(topic).or_default().push(info);
+        }
+    }
+    let mut groups: Vec<_> = by_topic
+        .into_iter()
+        .map(|(topic, group)| (topic.to_string(), group))
+        .collect();
+    groups.sort_by(|(a, a_group), (b, b_group)| {
+        b_group.len().cmp(&a_group.len()).then_with(|| a.cmp(b))
+    });
+    groups.push(("No topics".to_string(), untagged));
+    groups
+}
+
+/// Groups results into `--tiers` by dependents: one tier per threshold (at least that many, below
+/// the previous one) and a last tier for everything under the lowest threshold.
+fn group_by_dependents<'a>(
+    results: &'a [SponsorInfo],
+    thresholds: &[usize],
+) -> Vec<(String, Vec<&'a SponsorInfo>)> {
+    let mut thresholds: Vec<usize> = thresholds.to_vec();
+    thresholds.sort_unstable_by(|a, b| b.cmp(a));
+    thresholds.dedup();
+
+    let mut groups: Vec<(String, Vec<&SponsorInfo>)> = Vec::new();
+    let mut upper: Option<usize> = None;
+    for (i, &lower) in thresholds.iter().enumerate() {
+        let name = match i {
+            0 => "Critical".to_string(),
+            1 => "Important".to_string(),
+            _ => format!("Tier {}", i + 1),
+        };
+        let range = match upper {
+            Some(upper) if upper - 1 > lower => format!("{lower}-{}", upper - 1),
+            Some(_) => lower.to_string(),
+            None => format!("{lower}+"),
+        };
+        let members = results
+            .iter()
+            .filter(|r| r.dependents >= lower && upper.is_none_or(|u| r.dependents < u))
+            .collect();
+        groups.push((format!("{name} ({range} dependents)"), members));
+        upper = Some(lower);
+    }
+    let rest = results
+        .iter()
+        .filter(|r| upper.is_none_or(|u| r.dependents < u))
+        .collect();
+    let title = match upper {
+        Some(lowest) => format!("Other (fewer than {lowest} dependents)"),
+        None => String::new(),
+    };
+    groups.push((title, rest));
+    groups
+}
+
+/// Groups results by the owner of their repository, owners with the most crates first.
+fn group_by_owner(results: &[SponsorInfo]) -> Vec<(String, Vec<&SponsorInfo>)> {
+    // Keyed case-insensitively; the first spelling seen is the one shown.
+    let mut by_owner: HashMap<String, (String, Vec<&SponsorInfo>)> = HashMap::new();
+    for info in results {
+        let owner = extract_repo(&info.repository)
+            .map_or_else(|| info.repository.clone(), |(_, owner, _)| owner);
+        by_owner
+            .entry(owner.to_lowercase())
+            .or_insert_with(|| (owner, Vec::new()))
+            .1
+            .push(info);
+    }
+    let mut groups: Vec<_> = by_owner.into_values().collect();
+    groups.sort_by(|(a, a_group), (b, b_group)| {
+        b_group.len().cmp(&a_group.len()).then_with(|| a.cmp(b))
+    });
+    groups
+}
+
+/// Prints `--group-by owner`: each owner's most common funding link once, then their crates.
+fn print_owner_groups(results: &[SponsorInfo], style: TableStyle) {
+    if results.is_empty() {
+        println!("No sponsorable dependencies found.");
+        return;
+    }
+
+    let groups = group_by_owner(results);
+    println!("\n  {}\n", "💝 Sponsorable Dependencies".cyan().bold());
+    println!(
+        "  Found {} projects from {} owners you can support:\n",
+        results.len().bold(),
+        groups.len().bold()
+    );
+
+    for (owner, group) in &groups {
+        let mut link_counts: HashMap<&str, usize> = HashMap::new();
+        for info in group {
+            if let Some(link) = info.sponsor_links.first() {
+                *link_counts.entry(link).or_default() += 1;
+            }
+        }
+        let link = link_counts
+            .into_iter()
+            .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then_with(|| b.cmp(a)))
+            .map_or("-", |(link, _)| link);
+        let sponsors = match group.iter().find_map(|info| info.sponsor_count) {
+            Some(count) if style.humanize => humanize_count(count),
+            Some(count) => count.to_string(),
+            None => "-".to_string(),
+        };
+        println!(
+            "  {}  {}  {}",
+            owner.magenta().bold(),
+            link.blue().underline(),
+            format!("({sponsors} sponsors)").dimmed()
+        );
+        let names: Vec<&str> = group.iter().map(|info| info.name.as_str()).collect();
+        println!("    {}\n", names.join(", "));
+    }
+}
+
+/// Prints the rich report split into titled sections. An empty title prints its rows without a
+/// heading. `total` is the number of distinct projects, since a project may appear in several groups.
+fn print_grouped_results(total: usize, groups: &[(String, Vec<&SponsorInfo>)], style: TableStyle) {
+    if total == 0 {
+        println!("No sponsorable dependencies found.");
+        return;
+    }
+
+    println!("\n  {}\n", "💝 Sponsorable Dependencies".cyan().bold());
+    println!("  Found {} projects you can support:\n", total.bold());
+
+    for (title, group) in groups {
+        if group.is_empty() {
+            continue;
+        }
+        if !title.is_empty() {
+            println!("  {}\n", title.magenta().bold());
+        }
+        print_table(group, style);
+        println!();
+    }
+}
+
+/// Shortens a count for display: 999, 12.5k, 1.2M.
+fn humanize_count(count: u32) -> String {
+    let (scaled, suffix) = match count {
+        0..1_000 => return count.to_string(),
+        1_000..1_000_000 => (f64::from(count) / 1_000.0, "k"),
+        _ => (f64::from(count) / 1_000_000.0, "M"),
+    };
+    let text = format!("{scaled:.1}");
+    format!("{}{suffix}", text.strip_suffix(".0").unwrap_or(&text))
+}
+
+/// The Sponsors column: the count, plus the cheapest tier when `--show-tiers` found one.
+fn sponsors_cell(info: &SponsorInfo, humanize: bool) -> String {
+    let count = info.sponsor_count.map_or_else(
+        || "-".to_string(),
+        |c| {
+            if humanize {
+                humanize_count(c)
+            } else {
+                c.to_string()
+            }
+        },
+    );
+    match &info.min_tier {
+        Some(tier) => format!("{count} (from ${}/mo)", tier.monthly_price_in_dollars),
+        None => count,
+    }
+}
+
+/// The Kind column: how the workspace uses the package, e.g. `normal+build`.
+fn dep_kinds_cell(info: &SponsorInfo) -> String {
+    if info.dep_kinds.is_empty() {
+        return "-".to_string();
+    }
+    info.dep_kinds
+        .iter()
+        .map(|k| k.label())
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+fn print_table(results: &[&SponsorInfo], style: TableStyle) {
+    let columns = style.columns;
+    let cells: Vec<Vec<String>> = results
+        .iter()
+        .map(|info| columns.iter().map(|c| c.cell(info, style)).collect())
+        .collect();
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+                .max(column.min_width())
+        })
+        .collect();
+    // The last column isn't padded, so a long link doesn't leave trailing spaces.
+    let pad = |i: usize, text: &str| {
+        if i + 1 == columns.len() {
+            String::new()
+        } else {
+            " ".repeat(widths[i].saturating_sub(text.chars().count()))
+        }
+    };
+    let print_row = |row: Vec<String>| println!("  {}", row.join("  "));
+
+    print_row(
+        columns
+            .iter()
+            .enumerate()
+            .map(|(i, c)| format!("{}{}", c.header().bold(), pad(i, c.header())))
+            .collect(),
+    );
+    print_row(
+        widths
+            .iter()
+            .enumerate()
+            .map(|(i, &width)| {
+                if i + 1 == columns.len() {
+                    "─".repeat(width.max(40))
+                } else {
+                    "─".repeat(width)
+                }
+            })
+            .collect(),
+    );
+
+    for (info, row) in results.iter().copied().zip(&cells) {
+        let more = info.sponsor_links.len().saturating_sub(1);
+        print_row(
+            columns
+                .iter()
+                .enumerate()
+                .map(|(i, c)| {
+                    let mut cell = format!("{}{}", c.paint(&row[i]), pad(i, &row[i]));
+                    if *c == Column::Link && more > 0 && !style.show_all_links {
+                        cell.push_str(&format!(" (+{more} more)").dimmed().to_string());
+                    }
+                    cell
+                })
+                .collect(),
+        );
+        if style.show_all_links {
+            for link in info.sponsor_links.iter().skip(1) {
+                print_row(
+                    columns
+                        .iter()
+                        .enumerate()
+                        .map(|(i, c)| {
+                            let text = match c {
+                                Column::Platform => platform_label(link),
+                                Column::Link => link.clone(),
+                                _ => String::new(),
+                            };
+                            format!("{}{}", c.paint(&text), pad(i, &text))
+                        })
+                        .collect(),
+                );
+            }
+        }
+    }
+}
+
+/// Every distinct funding URL across the results, one per line, in result order.
+fn render_urls(results: &[SponsorInfo]) -> String {
+    let mut seen = HashSet::new();
+    let mut out = String::new();
+    for link in results.iter().flat_map(|r| &r.sponsor_links) {
+        if seen.insert(link.as_str()) {
+            out.push_str(link);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// One thank-you note per owner, naming the crates of theirs we depend on.
+fn render_thanks(results: &[SponsorInfo]) -> String {
+    let mut out = String::new();
+    for (owner, group) in group_by_owner(results) {
+        let owner = owner.strip_prefix("gitlab.com/").unwrap_or(&owner);
+        let names: Vec<String> = group
+            .iter()
+            .map(|info| format!("`{}`", info.name))
+            .collect();
+        let crates = match names.as_slice() {
+            [] => continue,
+            [one] => one.clone(),
+            [rest @ .., last] => format!("{} and {last}", rest.join(", ")),
+        };
+        let it = if names.len() == 1 { "it" } else { "them" };
+        out.push_str(&format!(
+            "Hi {owner}, thank you for your work on {crates}! My project depends on {it}, and this is a small way of saying thanks.\n"
+        ));
+        if let Some(link) = group.iter().find_map(|info| info.sponsor_links.first()) {
+            out.push_str(&format!("  {link}\n"));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Escapes text for a path segment of a shields.io static badge, where `-` and `_` separate
+/// the fields and `_` stands for a space.
+fn shields_segment(text: &str) -> String {
+    let text = text.replace('-', "--").replace('_', "__").replace(' ', "_");
+    url::form_urlencoded::byte_serialize(text.as_bytes()).collect()
+}
+
+/// A shields.io badge for a funding link. GitHub Sponsors badges show the owner's live sponsor
+/// count; other platforms get a static badge naming the platform.
+fn badge_url(name: &str, link: &str) -> String {
+    let label = format!("Sponsor {name}");
+    if Platform::classify(link) == Platform::GithubSponsors
+        && let Some(login) = Url::parse(link)
+            .ok()
+            .and_then(|url| url.path_segments()?.nth(1).map(str::to_string))
+            .filter(|login| !login.is_empty())
+    {
+        let query: String = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("label", &label)
+            .append_pair("logo", "githubsponsors")
+            .finish();
+        return format!("https://img.shields.io/github/sponsors/{login}?{query}");
+    }
+    format!(
+        "https://img.shields.io/badge/{}-{}-ea4aaa",
+        shields_segment(&label),
+        shields_segment(&platform_label(link))
+    )
+}
+
+/// `--emit-badges`: a Markdown badge per sponsorable crate, linking to its first funding link.
+/// Each is preceded by an HTML comment naming the crate, so the whole output can be pasted.
+fn render_badges(results: &[SponsorInfo]) -> String {
+    let mut out = String::new();
+    for info in results {
+        let Some(link) = info.sponsor_links.first() else {
+            continue;
+        };
+        let alt = format!("Sponsor {}", info.name)
+            .replace('\\', "\\\\")
+            .replace('[', "\\[")
+            .replace(']', "\\]");
+        let target = link
+            .replace(' ', "%20")
+            .replace('(', "%28")
+            .replace(')', "%29");
+        out.push_str(&format!(
+            "<!-- {} -->\n[![{alt}]({})]({target})\n\n",
+            info.name,
+            badge_url(&info.name, link)
+        ));
+    }
+    out
+}
+
+/// A block of TOML comments listing sponsorable dependencies, for pasting into `deny.toml` so
+/// reviewers see funding next to the allow/deny lists.
+fn render_cargo_deny(results: &[SponsorInfo]) -> String {
+    let mut out = String::from(
+        "# Sponsorable dependencies, generated by `cargo sponsor --output cargo-deny`.\n",
+    );
+    if results.is_empty() {
+        out.push_str("# (none found)\n");
+        return out;
+    }
+    out.push_str("# Consider supporting these projects when reviewing dependency changes.\n#\n");
+    let name_width = results.iter().map(|r| r.name.len()).max().unwrap_or(0);
+    for info in results {
+        let link = info.sponsor_links.first().map_or("-", String::as_str);
+        out.push_str(&format!("#   {:<name_width$}  {link}\n", info.name));
+    }
+    out
+}
+
+fn escape_markdown_cell(value: &str) -> String {
+    value.replace('|', "\\|")
+}
+
+fn render_markdown(results: &[SponsorInfo]) -> String {
+    if results.is_empty() {
+        return "No sponsorable dependencies found.\n".to_string();
+    }
+
+    let mut out = String::from(
+        "| Package | License | Owner sponsors | Used by | Platform | Link |\n| --- | --- | --- | --- | --- | --- |\n",
+    );
+    for info in results {
+        let sponsors = sponsors_cell(info, false);
+        let platform = primary_platform(info);
+        let link = info
+            .sponsor_links
+            .first()
+            .map_or_else(|| "-".to_string(), |l| format!("<{l}>"));
+        out.push_str(&format!(
+            "| {} | {} | {sponsors} | {} | {} | {} |\n",
+            escape_markdown_cell(&info.display_name()),
+            escape_markdown_cell(info.license.as_deref().unwrap_or("-")),
+            info.dependents,
+            escape_markdown_cell(&platform),
+            escape_markdown_cell(&link),
+        ));
+    }
+    out
+}
+
+/// Rows in a `--output pr-comment` table before the rest are summarized.
+const PR_COMMENT_MAX_ROWS: usize = 20;
+
+/// A pull request comment: one line of summary, the projects in a collapsed table, and a footer.
+fn render_pr_comment(results: &[SponsorInfo], since: Option<&str>) -> String {
+    let scope = since.map_or_else(
+        || "in this project".to_string(),
+        |git_ref| format!("added or upgraded since `{}`", git_ref.replace('`', "")),
     );
+    let mut out = String::new();
+    if results.is_empty() {
+        out.push_str(&format!("💝 No dependencies {scope} accept sponsorship.\n"));
+    } else {
+        let projects = if results.len() == 1 {
+            "1 dependency".to_string()
+        } else {
+            format!("{} dependencies", results.len())
+        };
+        out.push_str(&format!(
+            "💝 {projects} {scope} can be sponsored. Consider supporting the people who maintain them!\n\n"
+        ));
+        out.push_str("<details>\n<summary>Sponsorable dependencies</summary>\n\n");
+        out.push_str("| Package | Platform | Link |\n| --- | --- | --- |\n");
+        for info in results.iter().take(PR_COMMENT_MAX_ROWS) {
+            let link = info
+                .sponsor_links
+                .first()
+                .map_or_else(|| "-".to_string(), |l| format!("<{l}>"));
+            out.push_str(&format!(
+                "| {} | {} | {} |\n",
+                escape_markdown_cell(&info.display_name()),
+                escape_markdown_cell(&primary_platform(info)),
+                escape_markdown_cell(&link),
+            ));
+        }
+        if results.len() > PR_COMMENT_MAX_ROWS {
+            out.push_str(&format!(
+                "\n…and {} more\n",
+                results.len() - PR_COMMENT_MAX_ROWS
+            ));
+        }
+        out.push_str("\n</details>\n");
+    }
+    out.push_str(&format!(
+        "\n<sub>Found by [cargo-sponsor]({}).</sub>\n",
+        env!("CARGO_PKG_REPOSITORY")
+    ));
+    out
+}
+
+/// Quotes a CSV field when it contains a delimiter, quote, or line break (RFC 4180).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_row(fields: &[&str]) -> String {
+    let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+    fields.join(",") + "\n"
+}
+
+fn render_csv(results: &[SponsorInfo], columns: &[Column]) -> String {
+    let headers: Vec<&str> = columns.iter().map(|c| c.csv_header()).collect();
+    let mut out = csv_row(&headers);
+    for info in results {
+        let values: Vec<String> = columns
+            .iter()
+            .map(|c| c.value(info).unwrap_or_default())
+            .collect();
+        let values: Vec<&str> = values.iter().map(String::as_str).collect();
+        out.push_str(&csv_row(&values));
+    }
+    out
+}
+
+/// The `--full` report as CSV: every package with its license and funding status.
+fn render_full_csv(reports: &[PackageReport]) -> String {
+    let mut out = csv_row(&[
+        "package",
+        "version",
+        "license",
+        "repository",
+        "status",
+        "owner_sponsors",
+        "link",
+    ]);
+    for report in reports {
+        let status = serde_json::to_value(&report.status)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        out.push_str(&csv_row(&[
+            &report.name,
+            &report.version,
+            report.license.as_deref().unwrap_or(""),
+            report.repository.as_deref().unwrap_or(""),
+            &status,
+            &report
+                .sponsor_count
+                .map(|c| c.to_string())
+                .unwrap_or_default(),
+            report.sponsor_links.first().map_or("", String::as_str),
+        ]));
+    }
+    out
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
 
+/// The index page for `serve`: the same columns as the markdown table.
+fn render_html(results: &[SponsorInfo]) -> String {
+    let mut rows = String::new();
     for info in results {
-        let sponsor_str = info
+        let link = info.sponsor_links.first().map_or("-", String::as_str);
+        let sponsors = info
             .sponsor_count
             .map_or_else(|| "-".to_string(), |c| c.to_string());
-        let link = info
-            .sponsor_links
-            .first()
-            .map_or("-", std::string::String::as_str);
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{sponsors}</td><td>{}</td><td>{}</td><td><a href=\"{link}\">{link}</a></td></tr>\n",
+            escape_html(&info.display_name()),
+            escape_html(info.license.as_deref().unwrap_or("-")),
+            info.dependents,
+            escape_html(&primary_platform(info)),
+            link = escape_html(link),
+        ));
+    }
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Sponsorable dependencies</title></head><body>\n\
+         <h1>Sponsorable dependencies</h1>\n<p>{} projects. Raw data: <a href=\"/results.json\">results.json</a></p>\n\
+         <table>\n<tr><th>Package</th><th>License</th><th>Owner sponsors</th><th>Used by</th><th>Platform</th><th>Link</th></tr>\n{rows}</table>\n</body></html>\n",
+        results.len()
+    )
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DepsDevVersion {
+    #[serde(default)]
+    links: Vec<DepsDevLink>,
+    #[serde(default)]
+    related_projects: Vec<DepsDevRelatedProject>,
+}
+
+#[derive(Deserialize)]
+struct DepsDevLink {
+    label: String,
+    url: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DepsDevRelatedProject {
+    project_key: DepsDevProjectKey,
+    relation_type: String,
+}
+
+#[derive(Deserialize)]
+struct DepsDevProjectKey {
+    /// e.g. `github.com/serde-rs/serde`.
+    id: String,
+}
+
+/// What deps.dev knows about one package version.
+struct DepsDevInfo {
+    name: String,
+    repository: Option<String>,
+    funding_links: Vec<String>,
+}
+
+/// `--source deps-dev`: asks deps.dev about each package version. Packages it doesn't know, or
+/// that fail to load, are left out.
+async fn fetch_deps_dev(
+    http: &reqwest::Client,
+    deps: &[&Package],
+    concurrency: usize,
+) -> Vec<DepsDevInfo> {
+    let base = std::env::var(DEPS_DEV_API_URL_ENV).unwrap_or_else(|_| DEPS_DEV_API_URL.into());
+    let encode = |s: &str| url::form_urlencoded::byte_serialize(s.as_bytes()).collect::<String>();
+    let mut futures = FuturesUnordered::new();
+    let mut pending = deps.iter();
+    let mut found = Vec::new();
+    let mut failed = 0;
+
+    loop {
+        while futures.len() < concurrency
+            && let Some(package) = pending.next()
+        {
+            let url = format!(
+                "{base}/systems/cargo/packages/{}/versions/{}",
+                encode(package.name.as_str()),
+                encode(&package.version.to_string())
+            );
+            futures.push(async move {
+                let version: Result<Option<DepsDevVersion>> = async {
+                    let resp = http.get(&url).send().await?;
+                    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                        return Ok(None);
+                    }
+                    Ok(Some(resp.error_for_status()?.json().await?))
+                }
+                .await;
+                (package.name.to_string(), version)
+            });
+        }
+        let Some((name, version)) = futures.next().await else {
+            break;
+        };
+        let version = match version {
+            Ok(Some(version)) => version,
+            Ok(None) => continue,
+            Err(e) => {
+                debug!(package = %name, "deps.dev lookup failed: {e:#}");
+                failed += 1;
+                continue;
+            }
+        };
+        let repository = version
+            .links
+            .iter()
+            .find(|l| l.label == "SOURCE_REPO")
+            .map(|l| l.url.clone())
+            .or_else(|| {
+                version
+                    .related_projects
+                    .iter()
+                    .find(|p| p.relation_type == "SOURCE_REPO_TYPE")
+                    .map(|p| format!("https://{}", p.project_key.id))
+            });
+        let mut funding_links = Vec::new();
+        for link in version.links {
+            if Platform::classify(&link.url) != Platform::Custom
+                && !funding_links.contains(&link.url)
+            {
+                funding_links.push(link.url);
+            }
+        }
+        found.push(DepsDevInfo {
+            name,
+            repository,
+            funding_links,
+        });
+    }
+    if failed > 0 {
+        warn!("deps.dev lookups failed for {failed} package(s); run with -v for details");
+    }
+    found
+}
+
+/// Adds deps.dev's funding links to matching results, fills in repositories the results lack,
+/// and lists packages only deps.dev knows a funding link for.
+fn merge_deps_dev(results: &mut Vec<SponsorInfo>, deps: &[&Package], found: Vec<DepsDevInfo>) {
+    for entry in found {
+        if let Some(info) = results.iter_mut().find(|r| r.name == entry.name) {
+            if info.repository.is_empty()
+                && let Some(repository) = entry.repository
+            {
+                info.repository = repository;
+            }
+            for link in entry.funding_links {
+                if !info.sponsor_links.contains(&link) {
+                    info.sponsor_links.push(link);
+                }
+            }
+            continue;
+        }
+        let Some(package) = deps.iter().find(|p| p.name.as_str() == entry.name) else {
+            continue;
+        };
+        if entry.funding_links.is_empty() {
+            continue;
+        }
+        results.push(SponsorInfo {
+            name: entry.name,
+            version: None,
+            license: package.license.clone(),
+            repository: package
+                .repository
+                .clone()
+                .or(entry.repository)
+                .unwrap_or_default(),
+            sponsor_links: entry.funding_links,
+            sponsor_count: None,
+            dependents: 0,
+            last_activity: None,
+            topics: Vec::new(),
+            min_tier: None,
+            source: FundingSource::DepsDev,
+            dep_kinds: Vec::new(),
+            homepage: None,
+            documentation: None,
+            owner_type: OwnerType::Other,
+            packages: Vec::new(),
+            extra: serde_json::Map::new(),
+        });
+    }
+}
+
+#[derive(Deserialize)]
+struct CratesIoCrateResponse {
+    #[serde(rename = "crate")]
+    krate: CratesIoCrate,
+}
+
+#[derive(Deserialize)]
+struct CratesIoCrate {
+    repository: Option<String>,
+}
+
+/// `--verify-repo`: replaces each crates.io package's `repository` with the one crates.io has on
+/// record for the crate (its newest release), which catches versions published with a stale
+/// fork or an old location. Lookups that fail leave the package as it was.
+async fn verify_repositories(
+    http: &reqwest::Client,
+    metadata: &mut Metadata,
+    scanned: Option<&HashSet<PackageId>>,
+    pb: &ProgressBar,
+) {
+    let base = std::env::var(CRATES_IO_API_URL_ENV).unwrap_or_else(|_| CRATES_IO_API_URL.into());
+    let workspace: HashSet<PackageId> = metadata.workspace_members.iter().cloned().collect();
+    let mut checked: HashMap<String, Option<String>> = HashMap::new();
+    let mut interval = tokio::time::interval(CRATES_IO_REQUEST_INTERVAL);
+    let mut corrections = Vec::new();
+    let mut failed = 0;
+
+    let packages: Vec<&mut Package> = metadata
+        .packages
+        .iter_mut()
+        .filter(|p| {
+            !workspace.contains(&p.id)
+                && scanned.is_none_or(|s| s.contains(&p.id))
+                && p.source.as_ref().is_some_and(|s| s.is_crates_io())
+        })
+        .collect();
+    pb.set_length(packages.len() as u64);
+    for package in packages {
+        pb.set_message(package.name.to_string());
+        let name = package.name.to_string();
+        if !checked.contains_key(&name) {
+            interval.tick().await;
+            let url = format!(
+                "{base}/crates/{}",
+                url::form_urlencoded::byte_serialize(name.as_bytes()).collect::<String>()
+            );
+            let recorded: Result<CratesIoCrateResponse> = async {
+                Ok(http
+                    .get(&url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?)
+            }
+            .await;
+            let recorded = match recorded {
+                Ok(response) => response.krate.repository.filter(|r| !r.trim().is_empty()),
+                Err(e) => {
+                    debug!(package = %name, "crates.io lookup failed: {e:#}");
+                    failed += 1;
+                    None
+                }
+            };
+            checked.insert(name.clone(), recorded);
+        }
+        pb.inc(1);
+        let Some(recorded) = &checked[&name] else {
+            continue;
+        };
+        let declared = package.repository.as_deref().unwrap_or_default();
+        if normalize_repo_url(declared) != normalize_repo_url(recorded) {
+            corrections.push(format!(
+                "{}@{}: using crates.io's {recorded} instead of {}",
+                package.name,
+                package.version,
+                if declared.is_empty() {
+                    "no repository"
+                } else {
+                    declared
+                }
+            ));
+            package.repository = Some(recorded.clone());
+        }
+    }
+    pb.finish_and_clear();
+    for correction in corrections {
+        warn!("--verify-repo: {correction}");
+    }
+    if failed > 0 {
+        warn!("crates.io lookups failed for {failed} crate(s); run with -v for details");
+    }
+}
+
+/// Serializes records one at a time, so a record that can't be represented in JSON (a map with
+/// non-string keys, say) is dropped with a warning instead of taking the whole document with it.
+/// `--strict-json` makes that an error.
+fn json_array<T: Serialize>(
+    records: &[T],
+    name: impl Fn(&T) -> &str,
+    strict: bool,
+) -> Result<String> {
+    let mut values = Vec::with_capacity(records.len());
+    for record in records {
+        match serde_json::to_value(record) {
+            Ok(value) => values.push(value),
+            Err(e) if strict => {
+                return Err(e).with_context(|| format!("Failed to serialize {}", name(record)));
+            }
+            Err(e) => warn!("Leaving {} out of the JSON output: {e}", name(record)),
+        }
+    }
+    Ok(serde_json::to_string_pretty(&values)?)
+}
+
+/// Runs a `--format-script` over the results. The engine has no filesystem, network, or module
+/// access (`import` is disabled), and caps on operations and sizes stop runaway scripts, so a
+/// shared script can't do more than turn results into text.
+fn render_with_script(path: &Path, results: &[SponsorInfo]) -> Result<String> {
+    let script = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut engine = rhai::Engine::new();
+    engine.set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new());
+    engine.set_max_operations(50_000_000);
+    engine.set_max_call_levels(64);
+    engine.set_max_expr_depths(128, 128);
+    engine.set_max_string_size(64 << 20);
+    engine.set_max_array_size(1 << 20);
+    engine.set_max_map_size(1 << 20);
+    // Keep stdout for the script's result.
+    engine.on_print(|text| eprintln!("{text}"));
+    engine.on_debug(|text, _, pos| eprintln!("{pos:?}: {text}"));
+
+    let mut scope = rhai::Scope::new();
+    let results = rhai::serde::to_dynamic(results)
+        .map_err(|e| anyhow::anyhow!("Failed to pass results to the script: {e}"))?;
+    scope.push_constant("results", results);
+    let output: rhai::Dynamic = engine
+        .eval_with_scope(&mut scope, &script)
+        .map_err(|e| anyhow::anyhow!("{} failed: {e}", path.display()))?;
+    let type_name = output.type_name();
+    output.into_string().map_err(|_| {
+        anyhow::anyhow!(
+            "{} must return a string, but returned {type_name}",
+            path.display()
+        )
+    })
+}
+
+/// Escapes text for XML element content and attribute values of either quote style.
+fn escape_xml(value: &str) -> String {
+    escape_html(value).replace('\'', "&apos;")
+}
+
+fn render_opml(results: &[SponsorInfo]) -> String {
+    let mut outlines = String::new();
+    for info in results {
+        let funding = info.sponsor_links.first().map_or("", String::as_str);
+        outlines.push_str(&format!(
+            "    <outline type=\"link\" text=\"{}\" url=\"{}\" htmlUrl=\"{}\" description=\"{}\"/>\n",
+            escape_xml(&info.name),
+            escape_xml(&info.repository),
+            escape_xml(&info.repository),
+            escape_xml(&format!("{}: {funding}", primary_platform(info))),
+        ));
+    }
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<opml version=\"2.0\">\n  <head>\n    <title>Sponsorable dependencies</title>\n  </head>\n  <body>\n{outlines}  </body>\n</opml>\n"
+    )
+}
+
+fn render_rss(results: &[SponsorInfo]) -> String {
+    let mut items = String::new();
+    for info in results {
+        let link = info.sponsor_links.first().unwrap_or(&info.repository);
+        let sponsors = info
+            .sponsor_count
+            .map_or_else(String::new, |c| format!(", {c} owner sponsors"));
+        items.push_str(&format!(
+            "    <item>\n      <title>{}</title>\n      <link>{}</link>\n      <guid isPermaLink=\"false\">{}</guid>\n      <description>{}</description>\n    </item>\n",
+            escape_xml(&info.name),
+            escape_xml(link),
+            escape_xml(&info.repository),
+            escape_xml(&format!(
+                "{} (used by {}{sponsors}). Repository: {}",
+                primary_platform(info),
+                info.dependents,
+                info.repository
+            )),
+        ));
+    }
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\">\n  <channel>\n    <title>Sponsorable dependencies</title>\n    <link>{}</link>\n    <description>Dependencies you can sponsor, from cargo-sponsor</description>\n{items}  </channel>\n</rss>\n",
+        env!("CARGO_PKG_REPOSITORY")
+    )
+}
+
+/// Makes text safe inside a quoted Mermaid node label, using Mermaid's `#...;` entity codes.
+fn escape_mermaid(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '#' => out.push_str("#35;"),
+            '"' => out.push_str("#quot;"),
+            '<' => out.push_str("#lt;"),
+            '>' => out.push_str("#gt;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// A Mermaid flowchart: each workspace member points at the checked projects it depends on,
+/// green when they can be sponsored and grey when they declare no funding. `roots` pairs each
+/// member with the names of the packages it reaches, or `None` when there's no graph to tell.
+fn render_mermaid(
+    roots: &[(&str, Option<HashSet<&str>>)],
+    results: &[SponsorInfo],
+    unfunded: &[UnfundedRepo],
+) -> String {
+    let mut out = String::from("flowchart LR\n");
+    out.push_str("    classDef root fill:#dbeafe,stroke:#1d4ed8\n");
+    out.push_str("    classDef funded fill:#dcfce7,stroke:#15803d\n");
+    out.push_str("    classDef unfunded fill:#f3f4f6,stroke:#9ca3af,color:#6b7280\n");
+    for (i, (name, _)) in roots.iter().enumerate() {
+        out.push_str(&format!("    r{i}[\"{}\"]:::root\n", escape_mermaid(name)));
+    }
+
+    let mut nodes: Vec<(&str, String, &str)> = results
+        .iter()
+        .map(|info| {
+            let label = format!(
+                "{}<br/>{}",
+                escape_mermaid(&info.display_name()),
+                escape_mermaid(&primary_platform(info))
+            );
+            (info.name.as_str(), label, "funded")
+        })
+        .collect();
+    nodes.extend(
+        unfunded
+            .iter()
+            .map(|repo| (repo.name.as_str(), escape_mermaid(&repo.name), "unfunded")),
+    );
+    for (i, (name, label, class)) in nodes.iter().enumerate() {
+        out.push_str(&format!("    p{i}[\"{label}\"]:::{class}\n"));
+        for (j, (root, reaches)) in roots.iter().enumerate() {
+            if root != name && reaches.as_ref().is_none_or(|r| r.contains(name)) {
+                out.push_str(&format!("    r{j} --> p{i}\n"));
+            }
+        }
+    }
+    out
+}
+
+const FUNDING_LOCK_FILE: &str = "funding.lock";
+const FUNDING_LOCK_VERSION: u32 = 1;
+
+/// The file `cargo sponsor lock` writes: the funding links of every project in the report, by
+/// package name. Sponsor counts change too often to be worth tracking, so they're left out.
+#[derive(Deserialize, Serialize)]
+struct FundingLock {
+    version: u32,
+    #[serde(default, rename = "project")]
+    projects: Vec<LockedFunding>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct LockedFunding {
+    name: String,
+    repository: String,
+    links: Vec<String>,
+}
+
+impl FundingLock {
+    fn from_results(results: &[SponsorInfo]) -> Self {
+        let mut projects: BTreeMap<&str, LockedFunding> = BTreeMap::new();
+        for info in results {
+            let project = projects
+                .entry(info.name.as_str())
+                .or_insert_with(|| LockedFunding {
+                    name: info.name.clone(),
+                    repository: info.repository.clone(),
+                    links: Vec::new(),
+                });
+            // `--per-version` reports a crate once per version; lock them together.
+            for link in &info.sponsor_links {
+                if !project.links.contains(link) {
+                    project.links.push(link.clone());
+                }
+            }
+        }
+        Self {
+            version: FUNDING_LOCK_VERSION,
+            projects: projects.into_values().collect(),
+        }
+    }
+}
+
+/// `cargo sponsor lock`.
+fn write_funding_lock(path: &Path, results: &[SponsorInfo]) -> Result<()> {
+    let lock = FundingLock::from_results(results);
+    let text = format!(
+        "# Funding links of this project's dependencies, written by `cargo sponsor lock`.\n# `cargo sponsor check` fails when a fresh scan no longer matches; rerun `cargo sponsor lock` to accept the changes.\n\n{}",
+        toml::to_string(&lock)?
+    );
+    std::fs::write(path, text).with_context(|| format!("Failed to write {}", path.display()))?;
+    eprintln!(
+        "Wrote the funding links of {} projects to {}",
+        lock.projects.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// `cargo sponsor check`: lists each project that was added, removed, or whose links changed
+/// since the lock was written, and fails if there are any.
+fn check_funding_lock(path: &Path, results: &[SponsorInfo]) -> Result<()> {
+    let text = std::fs::read_to_string(path).with_context(|| {
+        format!(
+            "Failed to read {}; create it with `cargo sponsor lock`",
+            path.display()
+        )
+    })?;
+    let locked: FundingLock =
+        toml::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))?;
+    if locked.version != FUNDING_LOCK_VERSION {
+        anyhow::bail!(
+            "{} is version {}, but this cargo-sponsor reads version {FUNDING_LOCK_VERSION}",
+            path.display(),
+            locked.version
+        );
+    }
+    let current = FundingLock::from_results(results);
+    let old: BTreeMap<&str, &LockedFunding> = locked
+        .projects
+        .iter()
+        .map(|p| (p.name.as_str(), p))
+        .collect();
+    let new: BTreeMap<&str, &LockedFunding> = current
+        .projects
+        .iter()
+        .map(|p| (p.name.as_str(), p))
+        .collect();
+
+    let mut changes = Vec::new();
+    for (name, project) in &new {
+        match old.get(name) {
+            None => changes.push(format!(
+                "{} {name}: {}",
+                "+".green(),
+                project.links.join(", ")
+            )),
+            Some(before) if before.links != project.links => changes.push(format!(
+                "{} {name}: {} -> {}",
+                "~".yellow(),
+                before.links.join(", "),
+                project.links.join(", ")
+            )),
+            Some(_) => {}
+        }
+    }
+    for name in old.keys().filter(|name| !new.contains_key(*name)) {
+        changes.push(format!("{} {name}", "-".red()));
+    }
+
+    if changes.is_empty() {
         println!(
-            "  {:<name_width$}  {:<sponsors_width$}  {}",
-            info.name.yellow(),
-            sponsor_str.dimmed(),
-            link.blue().underline(),
+            "{}",
+            format!("Funding links match {}.", path.display()).green()
+        );
+        return Ok(());
+    }
+    for change in &changes {
+        println!("{change}");
+    }
+    anyhow::bail!(
+        "{} funding change(s) since {} was written; run `cargo sponsor lock` to accept them",
+        changes.len(),
+        path.display()
+    )
+}
+
+/// Serves the finished scan on `127.0.0.1:port`: the HTML index at `/` and the `--output json`
+/// document at `/results.json`. The data is computed once, so requests never reach GitHub.
+async fn serve(port: u16, json: String, html: String) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+        .await
+        .with_context(|| format!("Failed to listen on port {port}"))?;
+    eprintln!("Serving sponsor data on http://127.0.0.1:{port}/ (JSON at /results.json)");
+    let pages: Arc<(String, String)> = Arc::new((json, html));
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let pages = Arc::clone(&pages);
+        tokio::spawn(async move {
+            if let Err(e) = respond(stream, &pages.0, &pages.1).await {
+                debug!("Failed to answer {peer}: {e}");
+            }
+        });
+    }
+}
+
+/// Answers one HTTP/1.x request and closes the connection.
+async fn respond(mut stream: tokio::net::TcpStream, json: &str, html: &str) -> Result<()> {
+    use tokio::io::AsyncReadExt;
+
+    let mut buf = vec![0; 8192];
+    let len = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..len]);
+    let mut parts = request.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let path = path.split('?').next().unwrap_or(path);
+
+    let (status, content_type, body) = match (method, path) {
+        ("GET" | "HEAD", "/" | "/index.html") => ("200 OK", "text/html; charset=utf-8", html),
+        ("GET" | "HEAD", "/results.json") => ("200 OK", "application/json", json),
+        ("GET" | "HEAD", _) => ("404 Not Found", "text/plain", "Not found\n"),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Method not allowed\n",
+        ),
+    };
+    let mut response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    if method != "HEAD" {
+        response.push_str(body);
+    }
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+const ENRICH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Runs `program` with one result as JSON on stdin and returns the JSON object it prints.
+async fn run_enrich_cmd(
+    program: &Path,
+    info: &SponsorInfo,
+) -> Result<serde_json::Map<String, serde_json::Value>> {
+    let mut child = tokio::process::Command::new(program)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program.display()))?;
+
+    let input = serde_json::to_vec(info)?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let output = tokio::time::timeout(ENRICH_TIMEOUT, async move {
+        stdin.write_all(&input).await?;
+        drop(stdin);
+        child.wait_with_output().await
+    })
+    .await
+    .with_context(|| {
+        format!(
+            "{} timed out after {}",
+            program.display(),
+            humantime::format_duration(ENRICH_TIMEOUT)
+        )
+    })??;
+
+    if !output.status.success() {
+        anyhow::bail!("{} exited with {}", program.display(), output.status);
+    }
+    serde_json::from_slice(&output.stdout)
+        .with_context(|| format!("{} did not print a JSON object", program.display()))
+}
+
+/// Merges the output of `--enrich-cmd` into each result. Built-in fields can't be overwritten.
+async fn enrich_results(
+    program: &Path,
+    results: &mut [SponsorInfo],
+    concurrency: usize,
+    progress: &MultiProgress,
+) {
+    let pb = phase_bar(progress, "Running enrichment command", results.len() as u64);
+    let outputs: Vec<_> = futures::stream::iter(results.iter())
+        .map(|info| {
+            let pb = pb.clone();
+            async move {
+                let output = run_enrich_cmd(program, info).await;
+                pb.inc(1);
+                output
+            }
+        })
+        .buffered(concurrency)
+        .collect()
+        .await;
+    pb.finish_and_clear();
+
+    for (info, output) in results.iter_mut().zip(outputs) {
+        let fields = match output {
+            Ok(fields) => fields,
+            Err(e) => {
+                warn!("Failed to enrich {}: {e:#}", info.name);
+                continue;
+            }
+        };
+        let builtin = match serde_json::to_value(&*info) {
+            Ok(serde_json::Value::Object(map)) => map,
+            _ => serde_json::Map::new(),
+        };
+        for (key, value) in fields {
+            if builtin.contains_key(&key) && !info.extra.contains_key(&key) {
+                debug!(
+                    "Ignoring enrichment field {key} for {}: it is built in",
+                    info.name
+                );
+                continue;
+            }
+            info.extra.insert(key, value);
+        }
+    }
+}
+
+/// Escapes a value for use in a GitHub Actions workflow command message.
+fn escape_workflow_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes a value for use as a workflow command property such as `title=`.
+fn escape_workflow_property(value: &str) -> String {
+    escape_workflow_data(value)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+fn print_github_annotations(results: &[SponsorInfo]) {
+    if results.is_empty() {
+        println!("::notice title=cargo-sponsor::No sponsorable dependencies found.");
+        return;
+    }
+
+    println!(
+        "::notice title=cargo-sponsor::{}",
+        escape_workflow_data(&format!("Found {} sponsorable dependencies", results.len()))
+    );
+    for info in results {
+        let link = info.sponsor_links.first().map_or("-", String::as_str);
+        let sponsors = info
+            .sponsor_count
+            .map(|c| format!(" ({c} owner sponsors)"))
+            .unwrap_or_default();
+        println!(
+            "::notice title={}::{}",
+            escape_workflow_property(&format!("Sponsor {}", info.name)),
+            escape_workflow_data(&format!(
+                "{} can be supported at {link}{sponsors}",
+                info.name
+            )),
         );
     }
-    println!();
-}
+}
+
+/// Adds a labelled bar for one phase of the run to the shared progress display.
+fn phase_bar(progress: &MultiProgress, phase: &'static str, len: u64) -> ProgressBar {
+    let pb = progress.add(ProgressBar::new(len));
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} {prefix}... [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+            .expect("invalid progress bar template")
+            .progress_chars("#>-"),
+    );
+    pb.set_prefix(phase);
+    pb
+}
+
+/// Adds a spinner for a phase whose length isn't known, such as running `cargo metadata`.
+fn phase_spinner(progress: &MultiProgress, phase: &'static str) -> ProgressBar {
+    let pb = progress.add(ProgressBar::new_spinner());
+    pb.set_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner:.green} {prefix}...")
+            .expect("invalid progress bar template"),
+    );
+    pb.set_prefix(phase);
+    pb.enable_steady_tick(Duration::from_millis(100));
+    pb
+}
+
+async fn fetch_sponsor_info(
+    github: &Arc<GitHubClient>,
+    to_fetch: Vec<FetchTarget>,
+    concurrency: usize,
+    scan_readme: bool,
+    deadline: Option<Instant>,
+    progress: &MultiProgress,
+    mut checkpoint: Option<&mut Checkpoint>,
+) -> FetchOutcome {
+    let pb = phase_bar(
+        progress,
+        "Retrieving sponsor information",
+        to_fetch.len() as u64,
+    );
+    // READMEs are only fetched for repos without declared funding, so this bar grows as we go.
+    let readme_pb = scan_readme.then(|| phase_bar(progress, "Scanning READMEs", 0));
+
+    let mut outcome = FetchOutcome {
+        results: Vec::new(),
+        statuses: HashMap::new(),
+        truncated: false,
+        unfunded: Vec::new(),
+    };
+    let mut futures = FuturesUnordered::new();
+    let mut pending = to_fetch.into_iter();
+
+    loop {
+        while futures.len() < concurrency
+            && let Some(target) = pending.next()
+        {
+            let github = Arc::clone(github);
+            let pb = pb.clone();
+            let readme_pb = readme_pb.clone();
+
+            futures.push(async move {
+                pb.set_message(target.pkg_name.clone());
+                let mut result = match target.host {
+                    _ if github.fixture.is_some() => Ok(github
+                        .fixture
+                        .as_ref()
+                        .and_then(|f| f.get(&repo_key(&target.owner, &target.repo)))
+                        .cloned()),
+                    Host::Github => {
+                        get_repo_sponsor_info(&github, &target.owner, &target.repo).await
+                    }
+                    Host::Gitlab => {
+                        get_gitlab_funding_info(&github, &target.owner, &target.repo).await
+                    }
+                };
+                if let Some(readme_pb) = &readme_pb
+                    && target.host == Host::Github
+                    && !target.repo.is_empty()
+                    && github.fixture.is_none()
+                    && let Ok(Some(info)) = &mut result
+                    && info.funding_links.is_empty()
+                {
+                    readme_pb.inc_length(1);
+                    readme_pb.set_message(target.pkg_name.clone());
+                    let readme =
+                        get_readme_funding_links(&github, &target.owner, &target.repo).await;
+                    readme_pb.inc(1);
+                    match readme {
+                        Ok(links) if !links.is_empty() => {
+                            info.funding_links = links;
+                            info.source = FundingSource::Readme;
+                        }
+                        Ok(_) => {}
+                        Err(e) => debug!(
+                            "Failed to scan README for {}/{}: {e}",
+                            target.owner, target.repo
+                        ),
+                    }
+                }
+                pb.inc(1);
+                (target, result)
+            });
+        }
+
+        let next = match deadline {
+            Some(deadline) => {
+                if let Ok(next) = tokio::time::timeout_at(deadline, futures.next()).await {
+                    next
+                } else {
+                    outcome.truncated = true;
+                    break;
+                }
+            }
+            None => futures.next().await,
+        };
+        let Some((target, result)) = next else {
+            break;
+        };
+        if let Some(checkpoint) = checkpoint.as_deref_mut()
+            && let Ok(Some(info)) = &result
+        {
+            checkpoint.record(&target, info);
+        }
+        process_result(&mut outcome, target, result);
+    }
 
-async fn fetch_sponsor_info(
-    client: &reqwest::Client,
-    token: Option<&Arc<str>>,
-    to_fetch: Vec<(String, String, String, String)>,
-    concurrency: usize,
-) -> Vec<SponsorInfo> {
-    let pb = ProgressBar::new(to_fetch.len() as u64);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} Retrieving GitHub sponsor information... [{bar:40.cyan/blue}] {pos}/{len} {msg}")
-            .expect("invalid progress bar template")
-            .progress_chars("#>-"),
-    );
+    pb.finish_and_clear();
+    if let Some(readme_pb) = readme_pb {
+        readme_pb.finish_and_clear();
+    }
+    outcome
+}
 
-    let mut results: Vec<SponsorInfo> = Vec::new();
-    let mut futures = FuturesUnordered::new();
+/// A repository URL without the differences that don't change where it points: scheme, `www.`,
+/// a trailing slash or `.git`, and case.
+fn normalize_repo_url(url: &str) -> String {
+    let url = strip_scheme(url.trim())
+        .trim_start_matches("www.")
+        .trim_end_matches('/');
+    url.strip_suffix(".git").unwrap_or(url).to_lowercase()
+}
 
-    for (pkg_name, repo_url, owner, repo) in to_fetch {
-        let client = client.clone();
-        let token = token.cloned();
-        let pb = pb.clone();
+/// Two results are the same project when they point at the same repository, however it's spelled.
+fn merge_key(info: &SponsorInfo) -> String {
+    let repository = normalize_repo_url(&info.repository);
+    if repository.is_empty() {
+        format!("crate:{}", info.name)
+    } else {
+        repository
+    }
+}
 
-        futures.push(async move {
-            pb.set_message(pkg_name.clone());
-            let result = get_repo_sponsor_info(&client, &owner, &repo, token.as_ref()).await;
-            pb.inc(1);
-            (pkg_name, repo_url, owner, repo, result)
-        });
+/// Folds `other` into `into`, a result for the same repository from another scan. Sponsor counts
+/// are per owner account, so the newest (largest) one wins rather than the sum; dependents come
+/// from separate workspaces and add up.
+fn merge_result(into: &mut SponsorInfo, other: SponsorInfo) {
+    for name in std::iter::once(other.name).chain(other.packages) {
+        if !into.packages.contains(&name) {
+            into.packages.push(name);
+        }
+    }
+    for link in other.sponsor_links {
+        if !into.sponsor_links.contains(&link) {
+            into.sponsor_links.push(link);
+        }
+    }
+    for topic in other.topics {
+        if !into.topics.contains(&topic) {
+            into.topics.push(topic);
+        }
+    }
+    for kind in other.dep_kinds {
+        if !into.dep_kinds.contains(&kind) {
+            into.dep_kinds.push(kind);
+        }
+    }
+    into.dep_kinds.sort();
+    into.sponsor_count = into.sponsor_count.max(other.sponsor_count);
+    into.dependents += other.dependents;
+    // RFC 3339 timestamps from GitHub sort chronologically as strings.
+    into.last_activity = into.last_activity.take().max(other.last_activity);
+    if into.version != other.version {
+        into.version = None;
+    }
+    if into.owner_type == OwnerType::Other {
+        into.owner_type = other.owner_type;
+    }
+    into.license = into.license.take().or(other.license);
+    into.min_tier = into.min_tier.take().or(other.min_tier);
+    into.homepage = into.homepage.take().or(other.homepage);
+    into.documentation = into.documentation.take().or(other.documentation);
+    for (key, value) in other.extra {
+        into.extra.entry(key).or_insert(value);
+    }
+}
 
-        if futures.len() >= concurrency
-            && let Some((pkg_name, repo_url, owner, repo, result)) = futures.next().await
-        {
-            process_result(&mut results, pkg_name, repo_url, &owner, &repo, result);
+/// `cargo sponsor merge`: unions earlier `--output json` reports, one row per repository, and
+/// prints them like a scan would. `--full` reports can't be merged; they need the dependency graph.
+fn print_merged(args: &Args, files: &[PathBuf]) -> Result<()> {
+    let mut results: Vec<SponsorInfo> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for path in files {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let scan: Vec<SponsorInfo> = serde_json::from_str(&text).with_context(|| {
+            format!(
+                "{} is not a report from `cargo sponsor --output json`",
+                path.display()
+            )
+        })?;
+        for mut info in scan {
+            let key = merge_key(&info);
+            match index.get(&key) {
+                Some(&i) => merge_result(&mut results[i], info),
+                None => {
+                    if !info.packages.contains(&info.name) {
+                        info.packages.insert(0, info.name.clone());
+                    }
+                    index.insert(key, results.len());
+                    results.push(info);
+                }
+            }
+        }
+    }
+    // A repository only one package came from needs no list.
+    for info in &mut results {
+        if info.packages.len() == 1 {
+            info.packages.clear();
         }
     }
 
-    while let Some((pkg_name, repo_url, owner, repo, result)) = futures.next().await {
-        process_result(&mut results, pkg_name, repo_url, &owner, &repo, result);
+    if args.top_destinations {
+        return report_top_destinations(&results, args);
+    }
+    if let Some(key) = args.sort {
+        sort_results(&mut results, key, args.treat_unknown_as_zero, args.reverse);
     }
+    let omitted = args
+        .limit
+        .map_or(0, |limit| results.len().saturating_sub(limit));
+    results.truncate(results.len() - omitted);
 
-    pb.finish_and_clear();
-    results
+    let columns = args.columns.as_deref();
+    match args.output {
+        OutputFormat::Json => println!("{}", json_array(&results, |r| &r.name, args.strict_json)?),
+        OutputFormat::Markdown => print!("{}", render_markdown(&results)),
+        OutputFormat::Urls => print!("{}", render_urls(&results)),
+        OutputFormat::CargoDeny => print!("{}", render_cargo_deny(&results)),
+        OutputFormat::Csv => print!(
+            "{}",
+            render_csv(&results, columns.unwrap_or(DEFAULT_CSV_COLUMNS))
+        ),
+        OutputFormat::PrComment => print!("{}", render_pr_comment(&results, None)),
+        OutputFormat::Opml => print!("{}", render_opml(&results)),
+        OutputFormat::Rss => print!("{}", render_rss(&results)),
+        // Merged reports have no dependency graph, so there's nothing to draw edges from.
+        OutputFormat::Mermaid => print!("{}", render_mermaid(&[], &results, &[])),
+        OutputFormat::Github => print_github_annotations(&results),
+        OutputFormat::Rich => print_results(
+            &results,
+            TableStyle {
+                humanize: args.humanize,
+                show_all_links: args.show_all_links,
+                columns: columns.unwrap_or(DEFAULT_TABLE_COLUMNS),
+            },
+        ),
+    }
+    if omitted > 0 {
+        eprintln!("Note: {omitted} more results were omitted by --limit");
+    }
+    Ok(())
+}
+
+/// Cargo runs `cargo sponsor ...` as `cargo-sponsor sponsor ...`; running the binary directly
+/// leaves out that `sponsor`, so put it back to make both spellings parse the same.
+fn cargo_argv(args: impl IntoIterator<Item = std::ffi::OsString>) -> Vec<std::ffi::OsString> {
+    let mut args: Vec<_> = args.into_iter().collect();
+    if args.get(1).is_none_or(|arg| arg != "sponsor") {
+        args.insert(args.len().min(1), "sponsor".into());
+    }
+    args
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
+    let started = Instant::now();
+    let Cargo::Sponsor(args) = Cargo::parse_from(cargo_argv(std::env::args_os()));
+
+    // `-v` also shows our own debug logs, such as why individual lookups failed.
+    let filter = Targets::new().with_default(Level::INFO).with_target(
+        env!("CARGO_CRATE_NAME"),
+        if args.verbose {
+            Level::DEBUG
+        } else {
+            Level::INFO
+        },
+    );
+    let logs = tracing_subscriber::fmt::layer().with_writer(std::io::stderr);
+    let logs = match args.log_format {
+        LogFormat::Text => logs.boxed(),
+        LogFormat::Json => logs.json().boxed(),
+    };
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(logs)
+        .init();
+    GITHUB_HOST
+        .set(args.github_host.to_lowercase())
+        .expect("GitHub host set twice");
 
-    let Cargo::Sponsor(args) = Cargo::parse();
+    match &args.command {
+        Some(Command::Login) => return login(&args.github_host),
+        Some(Command::Logout) => return logout(&args.github_host),
+        Some(Command::Platforms) => return print_platforms(args.output),
+        Some(Command::Merge { files }) => return print_merged(&args, files),
+        Some(Command::Serve { .. } | Command::Lock { .. } | Command::Check { .. }) | None => {}
+    }
 
-    let manifest_path = if args.manifest_path.is_dir() {
-        args.manifest_path.join("Cargo.toml")
+    let progress = if args.quiet {
+        MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
     } else {
-        args.manifest_path
+        MultiProgress::new()
     };
 
-    let metadata = MetadataCommand::new()
-        .manifest_path(&manifest_path)
-        .exec()
-        .context("Failed to get cargo metadata")?;
+    let metadata_pb = phase_spinner(&progress, "Resolving dependency graph");
+    let mut embedded = None;
+    let metadata = if args.metadata_stdin {
+        std::io::read_to_string(std::io::stdin())
+            .context("Failed to read cargo metadata from stdin")
+            .and_then(|json| {
+                MetadataCommand::parse(json).context("Failed to parse cargo metadata from stdin")
+            })
+    } else if let Some(spec) = &args.crate_spec {
+        published_crate_metadata(spec)
+    } else if let Some(path) = &args.from_binary {
+        binary_metadata(path).map(|(metadata, packages)| {
+            embedded = Some(packages);
+            metadata
+        })
+    } else {
+        resolve_manifest_path(&args.manifest_path).and_then(|manifest_path| {
+            let mut command = MetadataCommand::new();
+            command.manifest_path(&manifest_path);
+            // Features decide which optional dependencies are in the resolved graph.
+            if args.all_features {
+                command.features(CargoOpt::AllFeatures);
+            }
+            if args.no_default_features {
+                command.features(CargoOpt::NoDefaultFeatures);
+            }
+            if !args.features.is_empty() {
+                command.features(CargoOpt::SomeFeatures(args.features.clone()));
+            }
+            command.exec().context("Failed to get cargo metadata")
+        })
+    };
+    metadata_pb.finish_and_clear();
+    let mut metadata = metadata?;
 
-    let client = reqwest::Client::builder()
+    let http = reqwest::Client::builder()
         .timeout(Duration::from_secs(30))
+        .user_agent(args.user_agent.as_deref().unwrap_or(USER_AGENT))
         .build()?;
-    let token = get_github_token();
+    if args.verify_repo {
+        // Only what the selected members use, so unrelated platform-specific crates cost nothing.
+        let scanned: Option<HashSet<PackageId>> = {
+            let roots = select_roots(&metadata, &args.package, args.workspace)?;
+            reachable_packages(&metadata, &roots).map(|r| r.into_iter().cloned().collect())
+        };
+        let pb = phase_bar(&progress, "Checking repositories on crates.io", 0);
+        verify_repositories(&http, &mut metadata, scanned.as_ref(), &pb).await;
+    }
+    let tokens: Vec<Arc<str>> = if args.token.is_empty() {
+        get_github_token(args.token_file.as_deref(), &args.github_host)?
+            .into_iter()
+            .collect()
+    } else {
+        args.token.iter().map(|t| Arc::from(t.trim())).collect()
+    };
+
+    if std::env::var_os("GITHUB_ACTIONS").is_some_and(|v| v == "true")
+        && !matches!(args.output, OutputFormat::Github)
+    {
+        eprintln!(
+            "Note: Running in GitHub Actions; use --output github to report sponsorable dependencies as workflow annotations"
+        );
+        eprintln!();
+    }
 
-    if token.is_none() {
+    if tokens.is_empty()
+        && args.fixture.is_none()
+        && args.source.contains(&DataSource::Github)
+        && should_show_token_hint(args.no_hints)
+    {
         eprintln!(
             "Note: Set GITHUB_TOKEN env var or install/auth the GitHub CLI for sponsor count info and FUNDING.yml parsing"
         );
         eprintln!();
     }
 
+    let (rest_url, graphql_url) = api_urls(&args.github_host);
+    let github = Arc::new(GitHubClient {
+        http,
+        graphql_url,
+        rest_url,
+        gitlab_token: std::env::var("GITLAB_TOKEN").ok().map(Arc::from),
+        tokens: tokens
+            .into_iter()
+            .map(|token| GitHubToken {
+                token,
+                limiter: RateLimiter::new(args.rate_limit_reserve, args.max_backoff),
+            })
+            .collect(),
+        next_token: AtomicUsize::new(0),
+        show_tiers: args.show_tiers,
+        include_owner_sponsors: args.include_owner_sponsors,
+        max_retries: args.max_retries,
+        owner_sponsors: Mutex::new(HashMap::new()),
+        http_cache: args.http_cache.clone(),
+        fixture: args.fixture.as_deref().map(load_fixture).transpose()?,
+        api_version: args.github_api_version.clone(),
+    });
+
     let root_packages: Vec<_> = metadata
         .workspace_members
         .iter()
@@ -408,35 +5485,842 @@ async fn main() -> Result<()> {
         .map(|p| p.name.clone())
         .collect();
 
+    let selected_roots = select_roots(&metadata, &args.package, args.workspace)?;
+    let mut reachable = reachable_packages(&metadata, &selected_roots);
+    if let Some(spec) = &args.subtree {
+        let subtree = select_subtree(&metadata, reachable.as_ref(), spec)?;
+        reachable = reachable_packages(&metadata, &[subtree]);
+    }
+
     let direct_deps: HashSet<_> = if args.top_level_only {
-        metadata
-            .workspace_members
+        selected_roots
             .iter()
-            .filter_map(|id| metadata.packages.iter().find(|p| &p.id == id))
             .flat_map(|p| p.dependencies.iter().map(|d| d.name.clone()))
             .collect()
     } else {
         HashSet::new()
     };
 
+    let kinds = dependency_kinds(&metadata, &selected_roots);
+    let previously_locked = args
+        .since
+        .as_deref()
+        .map(|git_ref| locked_packages_at(metadata.workspace_root.as_std_path(), git_ref))
+        .transpose()?;
     let deps: Vec<&Package> = metadata
         .packages
         .iter()
-        .filter(|p| !root_packages.contains(&p.name))
-        .filter(|p| !args.top_level_only || direct_deps.contains(p.name.as_str()))
+        .filter(|p| reachable.as_ref().is_none_or(|r| r.contains(&p.id)))
+        .filter(|p| {
+            if root_packages.contains(&p.name) {
+                args.include_self
+            } else {
+                !args.top_level_only || direct_deps.contains(p.name.as_str())
+            }
+        })
+        .filter(|p| {
+            previously_locked.as_ref().is_none_or(|old| {
+                !old.contains(&LockedPackage {
+                    name: p.name.to_string(),
+                    version: p.version.to_string(),
+                })
+            })
+        })
+        .filter(|p| {
+            embedded.as_ref().is_none_or(|packages| {
+                packages.contains(&LockedPackage {
+                    name: p.name.to_string(),
+                    version: p.version.to_string(),
+                })
+            })
+        })
+        .filter(|p| {
+            !args.only_build_deps
+                || kinds
+                    .get(&p.id)
+                    .is_some_and(|k| k.contains(&DepKind::Build))
+        })
         .collect();
 
-    let to_fetch = collect_repos_to_fetch(&deps);
-    let results = fetch_sponsor_info(&client, token.as_ref(), to_fetch, args.concurrency).await;
+    // Tell "nothing to check" apart from "checked, but nothing is fundable".
+    if deps.is_empty() {
+        let has_dependencies = metadata.packages.iter().any(|p| {
+            reachable.as_ref().is_none_or(|r| r.contains(&p.id)) && !root_packages.contains(&p.name)
+        });
+        let message = match &args.since {
+            Some(git_ref) if has_dependencies => {
+                format!("No dependencies were added or upgraded since {git_ref}.")
+            }
+            _ if has_dependencies => "No dependencies match the given filters.".to_string(),
+            _ => "No dependencies to analyze in this workspace.".to_string(),
+        };
+        if matches!(args.output, OutputFormat::Rich) {
+            println!("{message}");
+            return Ok(());
+        }
+        // Machine-readable formats still print their (empty) document below.
+        eprintln!("Note: {message}");
+        eprintln!();
+    }
 
-    match args.output {
-        OutputFormat::Json => {
-            println!("{}", serde_json::to_string_pretty(&results)?);
+    let ignore = IgnoreList::load(metadata.workspace_root.as_std_path(), &args.include)?;
+    let dependents = count_dependents(&metadata, args.use_homepage_fallback);
+    let (mut to_fetch, manifest_only) = collect_repos_to_fetch(
+        &deps,
+        &dependents,
+        args.use_homepage_fallback,
+        &args.hosts,
+        &args.exclude_repo_url,
+        &ignore,
+        if args.per_version {
+            RowsPer::Version
+        } else if args.no_dedup {
+            RowsPer::Package
+        } else {
+            RowsPer::Repository
+        },
+    );
+    if !args.source.contains(&DataSource::Github) {
+        to_fetch.clear();
+    }
+    // Lookups finish in any order, so remember where each repository belongs.
+    let mut fetch_order = HashMap::new();
+    if args.preserve_order {
+        let declared = declaration_order(&selected_roots);
+        to_fetch.sort_by_key(|t| declared.get(&t.pkg_name).copied().unwrap_or(usize::MAX));
+        for (i, target) in to_fetch.iter().enumerate() {
+            fetch_order.insert(target.pkg_name.clone(), i);
+        }
+    }
+    let mut resumed = Vec::new();
+    let mut checkpoint = None;
+    let to_fetch = if let Some(path) = &args.checkpoint {
+        let file = checkpoint.insert(if args.estimate {
+            Checkpoint::read(path)?
+        } else {
+            Checkpoint::open(path)?
+        });
+        let mut remaining = Vec::new();
+        for target in to_fetch {
+            match file.take_finished(&target) {
+                Some(info) => resumed.push((target, info)),
+                None => remaining.push(target),
+            }
+        }
+        if !resumed.is_empty() {
+            eprintln!(
+                "Note: Resuming from {}; {} repositories already checked",
+                path.display(),
+                resumed.len()
+            );
+            eprintln!();
+        }
+        remaining
+    } else {
+        to_fetch
+    };
+    if args.estimate {
+        let budget = match fetch_graphql_budget(&github).await {
+            Ok(budget) => budget,
+            Err(e) => {
+                warn!("Failed to check the GitHub rate limit: {e:#}");
+                None
+            }
+        };
+        print_estimate(
+            &to_fetch,
+            resumed.len(),
+            args.scan_readme,
+            budget.as_ref(),
+            args.rate_limit_reserve,
+        );
+        return Ok(());
+    }
+    let total = to_fetch.len();
+    if total > args.max_packages {
+        confirm_large_scan(total, args.max_packages)?;
+    }
+    let deadline = args.max_duration.map(|d| started + d);
+    // A single in-flight request keeps results in `to_fetch` order, which is itself stable.
+    let concurrency = if args.deterministic {
+        1
+    } else if args.concurrency > MAX_CONCURRENCY {
+        warn!(
+            "--concurrency {} is more than GitHub tolerates; using {MAX_CONCURRENCY}",
+            args.concurrency
+        );
+        MAX_CONCURRENCY
+    } else {
+        args.concurrency
+    };
+    let mut outcome = fetch_sponsor_info(
+        &github,
+        to_fetch,
+        concurrency,
+        args.scan_readme,
+        deadline,
+        &progress,
+        checkpoint.as_mut(),
+    )
+    .await;
+    for (target, info) in resumed {
+        process_result(&mut outcome, target, Ok(Some(info)));
+    }
+    outcome.results.extend(manifest_only);
+    if args.source.contains(&DataSource::DepsDev) {
+        let found = fetch_deps_dev(&github.http, &deps, concurrency).await;
+        merge_deps_dev(&mut outcome.results, &deps, found);
+    }
+    if args.preserve_order {
+        outcome
+            .results
+            .sort_by_key(|r| fetch_order.get(&r.name).copied().unwrap_or(usize::MAX));
+    }
+
+    let mut kinds_by_name: HashMap<&str, BTreeSet<DepKind>> = HashMap::new();
+    for package in &deps {
+        if let Some(package_kinds) = kinds.get(&package.id) {
+            kinds_by_name
+                .entry(package.name.as_str())
+                .or_default()
+                .extend(package_kinds);
+        }
+    }
+    for info in &mut outcome.results {
+        if let Some(package_kinds) = kinds_by_name.get(info.name.as_str()) {
+            info.dep_kinds = package_kinds.iter().copied().collect();
+        }
+        if let Some(package) = deps.iter().find(|p| {
+            p.name.as_str() == info.name
+                && info
+                    .version
+                    .as_ref()
+                    .is_none_or(|v| p.version.to_string() == *v)
+        }) {
+            info.homepage.clone_from(&package.homepage);
+            info.documentation.clone_from(&package.documentation);
+        }
+    }
+
+    if args.verbose {
+        for (i, token) in github.tokens.iter().enumerate() {
+            let (Some(remaining), reset, cost) = token.limiter.summary() else {
+                continue;
+            };
+            let which = if github.tokens.len() > 1 {
+                format!(" (token {})", i + 1)
+            } else {
+                String::new()
+            };
+            let resets = reset
+                .map(|reset| {
+                    let at = SystemTime::UNIX_EPOCH + Duration::from_secs(reset);
+                    format!(", resets at {}", humantime::format_rfc3339_seconds(at))
+                })
+                .unwrap_or_default();
+            eprintln!(
+                "GitHub API{which}: {remaining} requests left{resets}; this scan used {cost} GraphQL points"
+            );
+        }
+        eprintln!();
+    }
+
+    let mut failures: BTreeMap<FailureKind, usize> = BTreeMap::new();
+    for status in outcome.statuses.values() {
+        if let RepoStatus::Failed(kind, _) = status {
+            *failures.entry(*kind).or_default() += 1;
+        }
+    }
+    if args.verbose && !failures.is_empty() {
+        let counts: Vec<String> = failures
+            .iter()
+            .map(|(kind, count)| format!("{count} {}", kind.label()))
+            .collect();
+        eprintln!("Failed lookups: {}", counts.join(", "));
+        eprintln!();
+    }
+
+    if outcome.truncated {
+        eprintln!(
+            "Note: Scan stopped after {}; showing partial results ({} of {total} repositories checked)",
+            humantime::format_duration(args.max_duration.unwrap_or_default()),
+            outcome.statuses.len(),
+        );
+        eprintln!();
+    }
+
+    let score = funding_score(&outcome, &dependents);
+    if args.score_only {
+        let score =
+            score.context("No repositories could be checked, so there is no funding score")?;
+        println!("{score:.1}");
+        return Ok(());
+    }
+
+    // Computed before any display filters, so it describes the whole scan.
+    if let Some(path) = &args.export_anon {
+        std::fs::write(path, render_anon_summary(&outcome, score)? + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    if args.show_unfunded_popular {
+        let unfunded = &mut outcome.unfunded;
+        unfunded.sort_by(|a, b| {
+            b.dependents
+                .cmp(&a.dependents)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        let limit = args.limit.unwrap_or(usize::MAX);
+        let omitted = unfunded.len().saturating_sub(limit);
+        unfunded.truncate(limit);
+        match args.output {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(unfunded)?),
+            _ => print_unfunded(unfunded, omitted),
+        }
+        return Ok(());
+    }
+
+    let activity_cutoff = args.active_within.map(|d| SystemTime::now() - d);
+    if let Some(cutoff) = activity_cutoff {
+        outcome
+            .results
+            .retain(|r| is_active_since(r, cutoff) != Some(false));
+    }
+
+    if args.prefer_personal {
+        outcome.results.retain(|r| {
+            r.owner_type == OwnerType::User
+                && r.sponsor_links
+                    .iter()
+                    .any(|l| Platform::classify(l) == Platform::GithubSponsors)
+        });
+    }
+
+    if let Some(min) = args.min_sponsors {
+        // Without --treat-unknown-as-zero, owners without a listing aren't known to fall short.
+        outcome.results.retain(|r| {
+            comparable_sponsor_count(r, args.treat_unknown_as_zero).is_none_or(|c| c >= min)
+        });
+    }
+
+    if args.top_destinations {
+        return report_top_destinations(&outcome.results, &args);
+    }
+
+    if let Some(key) = args.sort {
+        sort_results(
+            &mut outcome.results,
+            key,
+            args.treat_unknown_as_zero,
+            args.reverse,
+        );
+    }
+
+    let mut omitted = 0;
+    if let Some(limit) = args.limit
+        && outcome.results.len() > limit
+    {
+        omitted = outcome.results.len() - limit;
+        outcome.results.truncate(limit);
+    }
+
+    if args.explain.is_some() {
+        explain_package(
+            &args,
+            &metadata,
+            reachable.as_ref(),
+            &direct_deps,
+            &deps,
+            &ignore,
+            &outcome,
+        );
+        eprintln!();
+    }
+
+    if let Some(program) = &args.enrich_cmd {
+        enrich_results(program, &mut outcome.results, concurrency, &progress).await;
+    }
+
+    let render_json = || -> Result<String> {
+        if args.full {
+            let report = build_full_report(&deps, &outcome, args.use_homepage_fallback);
+            json_array(&report, |r| &r.name, args.strict_json)
+        } else {
+            json_array(&outcome.results, |r| &r.name, args.strict_json)
+        }
+    };
+
+    let style = TableStyle {
+        humanize: args.humanize,
+        show_all_links: args.show_all_links,
+        columns: args.columns.as_deref().unwrap_or(if args.show_links {
+            SHOW_LINKS_COLUMNS
+        } else {
+            DEFAULT_TABLE_COLUMNS
+        }),
+    };
+    match &args.command {
+        Some(Command::Serve { port }) => {
+            return serve(*port, render_json()?, render_html(&outcome.results)).await;
+        }
+        Some(Command::Lock { lock_file }) => {
+            let default = metadata
+                .workspace_root
+                .as_std_path()
+                .join(FUNDING_LOCK_FILE);
+            return write_funding_lock(lock_file.as_deref().unwrap_or(&default), &outcome.results);
         }
-        OutputFormat::Rich => {
-            print_results(&results);
+        Some(Command::Check { lock_file }) => {
+            let default = metadata
+                .workspace_root
+                .as_std_path()
+                .join(FUNDING_LOCK_FILE);
+            return check_funding_lock(lock_file.as_deref().unwrap_or(&default), &outcome.results);
+        }
+        _ => {}
+    }
+
+    if args.emit_thanks {
+        print!("{}", render_thanks(&outcome.results));
+    } else if args.emit_badges {
+        print!("{}", render_badges(&outcome.results));
+    } else if let Some(path) = &args.format_script {
+        print!("{}", render_with_script(path, &outcome.results)?);
+    } else {
+        match args.output {
+            OutputFormat::Json => {
+                println!("{}", render_json()?);
+            }
+            OutputFormat::Markdown => {
+                print!("{}", render_markdown(&outcome.results));
+            }
+            OutputFormat::Urls => {
+                print!("{}", render_urls(&outcome.results));
+            }
+            OutputFormat::CargoDeny => {
+                print!("{}", render_cargo_deny(&outcome.results));
+            }
+            OutputFormat::Csv => {
+                if args.full {
+                    let report = build_full_report(&deps, &outcome, args.use_homepage_fallback);
+                    print!("{}", render_full_csv(&report));
+                } else {
+                    print!(
+                        "{}",
+                        render_csv(
+                            &outcome.results,
+                            args.columns.as_deref().unwrap_or(if args.show_links {
+                                SHOW_LINKS_COLUMNS
+                            } else {
+                                DEFAULT_CSV_COLUMNS
+                            })
+                        )
+                    );
+                }
+            }
+            OutputFormat::PrComment => {
+                print!(
+                    "{}",
+                    render_pr_comment(&outcome.results, args.since.as_deref())
+                );
+            }
+            OutputFormat::Opml => {
+                print!("{}", render_opml(&outcome.results));
+            }
+            OutputFormat::Mermaid => {
+                let roots: Vec<_> = selected_roots
+                    .iter()
+                    .map(|root| {
+                        let reaches = reachable_packages(&metadata, &[root]).map(|ids| {
+                            metadata
+                                .packages
+                                .iter()
+                                .filter(|p| ids.contains(&p.id))
+                                .map(|p| p.name.as_str())
+                                .collect()
+                        });
+                        (root.name.as_str(), reaches)
+                    })
+                    .collect();
+                print!(
+                    "{}",
+                    render_mermaid(&roots, &outcome.results, &outcome.unfunded)
+                );
+            }
+            OutputFormat::Rss => {
+                print!("{}", render_rss(&outcome.results));
+            }
+            OutputFormat::Github => {
+                print_github_annotations(&outcome.results);
+            }
+            OutputFormat::Rich => {
+                if let Some(GroupBy::Topic) = args.group_by {
+                    print_grouped_results(
+                        outcome.results.len(),
+                        &group_by_topic(&outcome.results),
+                        style,
+                    );
+                } else if let Some(GroupBy::Owner) = args.group_by {
+                    print_owner_groups(&outcome.results, style);
+                } else if args.tiers {
+                    print_grouped_results(
+                        outcome.results.len(),
+                        &group_by_dependents(&outcome.results, &args.tier_thresholds),
+                        style,
+                    );
+                } else if let Some(Partition::GithubSponsors) = args.partition {
+                    let (github, other): (Vec<_>, Vec<_>) = outcome.results.iter().partition(|r| {
+                        r.sponsor_links
+                            .iter()
+                            .any(|l| Platform::classify(l) == Platform::GithubSponsors)
+                    });
+                    print_grouped_results(
+                        outcome.results.len(),
+                        &[
+                            ("GitHub Sponsors".to_string(), github),
+                            ("Other platforms".to_string(), other),
+                        ],
+                        style,
+                    );
+                } else if let Some(cutoff) = activity_cutoff {
+                    let (active, unknown): (Vec<_>, Vec<_>) = outcome
+                        .results
+                        .iter()
+                        .partition(|r| is_active_since(r, cutoff).is_some());
+                    print_grouped_results(
+                        outcome.results.len(),
+                        &[
+                            (String::new(), active),
+                            ("Last activity unknown".to_string(), unknown),
+                        ],
+                        style,
+                    );
+                } else {
+                    print_results(&outcome.results, style);
+                }
+                if omitted > 0 {
+                    println!(
+                        "  {}\n",
+                        format!("…and {omitted} more not shown (raise --limit to see them)")
+                            .dimmed()
+                    );
+                }
+                if outcome
+                    .results
+                    .iter()
+                    .any(|r| r.owner_type == OwnerType::Organization && r.sponsor_count.is_some())
+                {
+                    println!(
+                        "  {}\n",
+                        "Owner sponsors are counted per GitHub account: an organization's count covers all of its projects, not only the crate listed."
+                            .dimmed()
+                    );
+                }
+                if let Some(score) = score {
+                    println!(
+                        "  Funding score: {}/100 {}\n",
+                        format!("{score:.1}").bold(),
+                        "(sponsorable share of checked repositories, weighted by dependents)"
+                            .dimmed()
+                    );
+                }
+            }
         }
     }
 
+    if omitted > 0 && !matches!(args.output, OutputFormat::Rich) {
+        eprintln!("Note: {omitted} more results were omitted by --limit");
+    }
+
+    if let Some(path) = &args.also_json {
+        std::fs::write(path, render_json()? + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    if let Some(path) = &args.also_markdown {
+        std::fs::write(path, render_markdown(&outcome.results))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    if args.open_all {
+        open_all(&outcome.results).await?;
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn graphql_rate_limit_reaches_the_limiter() {
+        let reset = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 3600;
+        let response: GitHubResponse = serde_json::from_value(serde_json::json!({
+            "data": {
+                "repository": null,
+                "rateLimit": {
+                    "cost": 1,
+                    "remaining": 4321,
+                    "resetAt": humantime::format_rfc3339(
+                        SystemTime::UNIX_EPOCH + Duration::from_secs(reset)
+                    )
+                    .to_string(),
+                },
+            },
+        }))
+        .unwrap();
+        let limiter = RateLimiter::new(Some(5000), None);
+        limiter.record_graphql(response.data.unwrap().rate_limit.as_ref().unwrap());
+        assert_eq!(limiter.summary(), (Some(4321), Some(reset), 1));
+        assert!(limiter.is_exhausted());
+    }
+
+    #[tokio::test]
+    async fn spent_budget_is_waited_out_without_a_reserve() {
+        let limiter = RateLimiter::new(None, Some(Duration::from_secs(1)));
+        let reset = SystemTime::now() + Duration::from_secs(3600);
+        limiter.record_graphql(&GraphqlRateLimit {
+            cost: 1,
+            remaining: 0,
+            reset_at: humantime::format_rfc3339(reset).to_string(),
+        });
+        assert!(limiter.is_exhausted());
+        // Waiting for the reset would take longer than --max-backoff allows.
+        assert!(matches!(
+            limiter.wait_for_budget().await,
+            Err(FetchError::BackoffTooLong { .. })
+        ));
+    }
+
+    #[test]
+    fn funding_yml_with_byte_order_mark() {
+        let links = parse_funding_yml("\u{feff}github: dtolnay\nko_fi: someone\n").unwrap();
+        assert_eq!(
+            links,
+            [
+                "https://github.com/sponsors/dtolnay",
+                "https://ko-fi.com/someone"
+            ]
+        );
+    }
+
+    #[test]
+    fn funding_yml_with_crlf_line_endings() {
+        let links = parse_funding_yml(
+            "github: [alice, bob]\r\npatreon: # placeholder\r\nliberapay: carol\r\n",
+        )
+        .unwrap();
+        assert_eq!(
+            links,
+            [
+                "https://github.com/sponsors/alice",
+                "https://github.com/sponsors/bob",
+                "https://liberapay.com/carol"
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_funding_yml() {
+        assert!(parse_funding_yml("").unwrap().is_empty());
+        assert!(
+            parse_funding_yml("# nothing here yet\n")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn malformed_funding_yml() {
+        assert!(parse_funding_yml("github: [alice\nko_fi: bob\n").is_err());
+        assert!(parse_funding_yml("- github\n- ko_fi\n").is_err());
+    }
+
+    fn owner(json: &str) -> OwnerData {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn sponsor_count_without_listing() {
+        let owner = owner(
+            r#"{"__typename": "User", "login": "alice", "hasSponsorsListing": false, "sponsors": {"totalCount": 3}}"#,
+        );
+        assert_eq!(owner.sponsor_count(), None);
+    }
+
+    #[test]
+    fn sponsor_count_with_empty_listing() {
+        let owner = owner(
+            r#"{"__typename": "User", "login": "alice", "hasSponsorsListing": true, "sponsors": {"totalCount": 0}}"#,
+        );
+        assert_eq!(owner.sponsor_count(), Some(0));
+    }
+
+    #[test]
+    fn sponsor_count_with_sponsors() {
+        let owner = owner(
+            r#"{"__typename": "Organization", "login": "tokio-rs", "hasSponsorsListing": true, "sponsors": {"totalCount": 42}}"#,
+        );
+        assert_eq!(owner.sponsor_count(), Some(42));
+    }
+
+    fn package(name: &str, repository: &str) -> Package {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "version": "1.0.0",
+            "id": format!("registry+https://github.com/rust-lang/crates.io-index#{name}@1.0.0"),
+            "manifest_path": format!("/registry/{name}-1.0.0/Cargo.toml"),
+            "repository": repository,
+            "dependencies": [],
+            "targets": [],
+            "features": {},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn mixed_case_repository_urls_share_a_target() {
+        let first = package("foo", "https://github.com/Owner/Repo");
+        let second = package("foo-derive", "https://github.com/owner/repo");
+        let dependents = HashMap::from([(repo_key("Owner", "Repo"), 2)]);
+        let (to_fetch, manifest_only) = collect_repos_to_fetch(
+            &[&first, &second],
+            &dependents,
+            false,
+            &[Host::Github],
+            &[],
+            &IgnoreList::default(),
+            RowsPer::Package,
+        );
+        assert!(manifest_only.is_empty());
+        assert_eq!(to_fetch.len(), 1);
+        let target = &to_fetch[0];
+        assert_eq!(
+            (target.owner.as_str(), target.repo.as_str()),
+            ("Owner", "Repo")
+        );
+        assert_eq!(target.repo_url, "https://github.com/Owner/Repo");
+        assert_eq!(target.dependents, 2);
+        assert_eq!(target.shared_with.len(), 1);
+        assert_eq!(target.shared_with[0].name, "foo-derive");
+    }
+
+    #[test]
+    fn github_repository_url_shapes() {
+        let cases = [
+            (
+                "https://github.com/serde-rs/serde",
+                Some(("serde-rs", "serde")),
+            ),
+            (
+                "https://github.com/serde-rs/serde/",
+                Some(("serde-rs", "serde")),
+            ),
+            (
+                "https://github.com/serde-rs/serde?tab=readme",
+                Some(("serde-rs", "serde")),
+            ),
+            (
+                "https://github.com/serde-rs/serde#readme",
+                Some(("serde-rs", "serde")),
+            ),
+            (
+                "https://github.com/serde-rs/serde.git/",
+                Some(("serde-rs", "serde")),
+            ),
+            (
+                "https://www.github.com/serde-rs/serde",
+                Some(("serde-rs", "serde")),
+            ),
+            (
+                "https://github.com//serde-rs//serde",
+                Some(("serde-rs", "serde")),
+            ),
+            (
+                "https://github.com/tokio-rs/tokio/tree/main/tokio-util",
+                Some(("tokio-rs", "tokio")),
+            ),
+            (
+                "  https://github.com/serde-rs/serde\n",
+                Some(("serde-rs", "serde")),
+            ),
+            ("https://github.com/serde-rs", None),
+            ("https://github.com/sponsors/dtolnay", None),
+            ("https://gitlab.com/serde-rs/serde", None),
+        ];
+        for (url, expected) in cases {
+            let expected = expected.map(|(owner, repo)| (owner.to_string(), repo.to_string()));
+            assert_eq!(extract_github_repo(url), expected, "{url}");
+        }
+    }
+
+    fn parse_args(argv: &[&str]) -> Args {
+        let Cargo::Sponsor(args) =
+            Cargo::try_parse_from(cargo_argv(argv.iter().map(Into::into))).unwrap();
+        args
+    }
+
+    #[test]
+    fn cargo_and_direct_invocations_parse_the_same() {
+        for argv in [
+            &[
+                "cargo-sponsor",
+                "sponsor",
+                "--output",
+                "json",
+                "--limit",
+                "3",
+            ][..],
+            &["cargo-sponsor", "--output", "json", "--limit", "3"],
+        ] {
+            let args = parse_args(argv);
+            assert!(matches!(args.output, OutputFormat::Json), "{argv:?}");
+            assert_eq!(args.limit, Some(3), "{argv:?}");
+            assert!(args.command.is_none(), "{argv:?}");
+        }
+        assert!(parse_args(&["cargo-sponsor"]).command.is_none());
+        assert!(parse_args(&["cargo-sponsor", "sponsor"]).command.is_none());
+    }
+
+    #[derive(Serialize)]
+    struct Record {
+        name: &'static str,
+        /// JSON object keys must be strings, so a non-empty map like this can't be serialized.
+        coordinates: BTreeMap<(u8, u8), u8>,
+    }
+
+    fn records() -> [Record; 2] {
+        [
+            Record {
+                name: "good",
+                coordinates: BTreeMap::new(),
+            },
+            Record {
+                name: "bad",
+                coordinates: BTreeMap::from([((1, 2), 3)]),
+            },
+        ]
+    }
+
+    #[test]
+    fn unserializable_record_is_skipped() {
+        let json = json_array(&records(), |r| r.name, false).unwrap();
+        // Fields keep their declaration order rather than being sorted.
+        assert!(json.find("\"name\"") < json.find("\"coordinates\""));
+        let values: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            values,
+            [serde_json::json!({"name": "good", "coordinates": {}})]
+        );
+    }
+
+    #[test]
+    fn unserializable_record_fails_strict_json() {
+        let err = json_array(&records(), |r| r.name, true).unwrap_err();
+        assert_eq!(err.to_string(), "Failed to serialize bad");
+    }
+}